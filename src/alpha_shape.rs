@@ -0,0 +1,197 @@
+use crate::convex_hull_3d::HullMesh;
+use crate::delaunay::{delaunay_3d, Tetrahedralization, FACES};
+use crate::error::{Error, Result};
+use crate::geometry::Point;
+
+// 3D alpha shape, a tighter outline of the plant than the convex hull.
+// the shape is the union of the Delaunay tetrahedra whose circumsphere radius is at most alpha.
+// a large alpha keeps every tetrahedron (the convex hull), a small one only keeps the dense parts
+// of the points and leaves out the air between the leaves.
+#[derive(Clone, Default)]
+pub struct AlphaShape {
+    // the boundary of the tetrahedra that are kept, a closed mesh wound outward. it can be made of
+    // several parts, that touch at an edge or a vertex.
+    pub mesh: HullMesh,
+    // sum of the volumes of the tetrahedra that are kept
+    pub volume: f64,
+}
+
+pub fn alpha_shape(points: &[Point], alpha: f64) -> Result<AlphaShape> {
+    if alpha.is_nan() || alpha <= 0.0 {
+        return Err(Error::InvalidArgument(format!(
+            "alpha {} must be positive",
+            alpha
+        )));
+    }
+    let Tetrahedralization {
+        tetrahedra,
+        neighbors,
+    } = delaunay_3d(points)?;
+    let kept: Vec<bool> = tetrahedra
+        .iter()
+        .map(|tet| {
+            let (radius, _) = circumsphere(points, tet);
+            radius <= alpha
+        })
+        .collect();
+
+    let mut shape = AlphaShape::default();
+    // index of the points in the mesh vertices
+    let mut index = vec![u32::MAX; points.len()];
+    for (t, tet) in tetrahedra.iter().enumerate() {
+        if !kept[t] {
+            continue;
+        }
+        shape.volume += circumsphere(points, tet).1;
+        for (i, face) in FACES.iter().enumerate() {
+            // a face is on the boundary when the tetrahedron on the other side is not kept
+            if neighbors[t][i].is_some_and(|n| kept[n]) {
+                continue;
+            }
+            let face = face.map(|j| {
+                let v = tet[j];
+                if index[v] == u32::MAX {
+                    index[v] = shape.mesh.vertices.len() as u32;
+                    shape.mesh.vertices.push(points[v]);
+                }
+                index[v]
+            });
+            shape.mesh.faces.push(face);
+        }
+    }
+    Ok(shape)
+}
+
+// radius of the circumsphere and volume of the tetrahedron.
+// the center is at a + (|u|^2 (v X w) + |v|^2 (w X u) + |w|^2 (u X v)) / (2 u . (v X w))
+// with u, v, w the edges from a.
+fn circumsphere(points: &[Point], tet: &[usize; 4]) -> (f64, f64) {
+    let a = &points[tet[0]];
+    let edge = |i: usize| {
+        let pt = &points[tet[i]];
+        [pt.x - a.x, pt.y - a.y, pt.z - a.z]
+    };
+    let (u, v, w) = (edge(1), edge(2), edge(3));
+    let cross = |p: [f64; 3], q: [f64; 3]| {
+        [
+            p[1] * q[2] - p[2] * q[1],
+            p[2] * q[0] - p[0] * q[2],
+            p[0] * q[1] - p[1] * q[0],
+        ]
+    };
+    let dot = |p: [f64; 3], q: [f64; 3]| p[0] * q[0] + p[1] * q[1] + p[2] * q[2];
+    let (vw, wu, uv) = (cross(v, w), cross(w, u), cross(u, v));
+    let det = dot(u, vw);
+    let (lu, lv, lw) = (dot(u, u), dot(v, v), dot(w, w));
+    let center: Vec<f64> = (0..3)
+        .map(|k| (lu * vw[k] + lv * wu[k] + lw * uv[k]) / (2.0 * det))
+        .collect();
+    let radius = (center[0].powi(2) + center[1].powi(2) + center[2].powi(2)).sqrt();
+    // the tetrahedra are positively oriented, the triple product is negative (see orient3d)
+    (radius, (det / 6.0).abs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::alpha_shape;
+    use crate::convex_hull_3d::{convex_hull, convex_hull_volume};
+    use crate::geometry::Point;
+    use std::collections::HashMap;
+
+    // a grid of spacing 0.5 on [0, 4] x [0, 4] x [0, 2], without the middle of the top half:
+    // a box with a square hole from the top
+    fn cup() -> Vec<Point> {
+        let mut data = vec![];
+        for i in 0..=8 {
+            for j in 0..=8 {
+                for k in 0..=4 {
+                    let (x, y, z) = (i as f64 * 0.5, j as f64 * 0.5, k as f64 * 0.5);
+                    if z > 1.0 && x > 1.0 && x < 3.0 && y > 1.0 && y < 3.0 {
+                        continue;
+                    }
+                    data.push(Point::new(x, y, z));
+                }
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn test_alpha_shape_cup() {
+        let data = cup();
+        let convex = convex_hull_volume(&convex_hull(data.clone()).unwrap());
+        assert!((convex - 32.0).abs() < 0.000001);
+
+        // the hole is 2 x 2 x 1, less the grid cells along its inner edges that still have 6 or 7
+        // corners: 12 half cells and 4 cells without a corner tetrahedron (1 / 48)
+        let shape = alpha_shape(&data, 0.5).unwrap();
+        let filled = 12.0 * 0.0625 + 4.0 * (0.125 - 1.0 / 48.0);
+        let expected = 32.0 - 4.0 + filled;
+        assert!(
+            (shape.volume - expected).abs() < 0.000001,
+            "{}",
+            shape.volume
+        );
+
+        // a large alpha is the convex hull
+        let shape = alpha_shape(&data, 100.0).unwrap();
+        assert!((shape.volume - convex).abs() < 0.000001);
+    }
+
+    #[test]
+    fn test_alpha_shape_closed_mesh() {
+        let shape = alpha_shape(&cup(), 0.5).unwrap();
+        // every directed edge has its reverse in another face
+        let mut edges: HashMap<(u32, u32), i32> = HashMap::new();
+        for face in &shape.mesh.faces {
+            for k in 0..3 {
+                let (a, b) = (face[k], face[(k + 1) % 3]);
+                *edges.entry((a, b)).or_default() += 1;
+                *edges.entry((b, a)).or_default() -= 1;
+            }
+        }
+        assert!(edges.values().all(|count| *count == 0));
+        // the boundary is wound outward, so the signed volume of the faces is the volume
+        let volume = shape.mesh.volume();
+        assert!((volume - shape.volume).abs() < 0.000001, "{}", volume);
+    }
+
+    #[test]
+    fn test_alpha_shape_l_block() {
+        // a grid of spacing 0.5 on [0, 4] x [0, 2] x [0, 2], with [0, 2] x [0, 2] x [2, 4] on top
+        let mut data = vec![];
+        for i in 0..=8 {
+            for j in 0..=4 {
+                for k in 0..=8 {
+                    let (x, y, z) = (i as f64 * 0.5, j as f64 * 0.5, k as f64 * 0.5);
+                    if z <= 2.0 || x <= 2.0 {
+                        data.push(Point::new(x, y, z));
+                    }
+                }
+            }
+        }
+        // 16 + 8, and the 4 grid cells along the inner edge that have 6 corners are half filled
+        let shape = alpha_shape(&data, 0.5).unwrap();
+        let expected = 24.0 + 4.0 * 0.0625;
+        assert!(
+            (shape.volume - expected).abs() < 0.000001,
+            "{}",
+            shape.volume
+        );
+        // the mesh is not convex, its volume is still the one of the tetrahedra
+        let volume = shape.mesh.volume();
+        assert!((volume - shape.volume).abs() < 0.000001, "{}", volume);
+        let convex = convex_hull_volume(&convex_hull(data).unwrap());
+        assert!((convex - 28.0).abs() < 0.000001, "{}", convex);
+    }
+
+    #[test]
+    fn test_alpha_shape_invalid() {
+        assert!(alpha_shape(&cup(), 0.0).is_err());
+        assert!(alpha_shape(&cup(), f64::NAN).is_err());
+        // far apart points, no tetrahedron is small enough
+        let shape = alpha_shape(&cup(), 0.1).unwrap();
+        assert_eq!(shape.volume, 0.0);
+        assert!(shape.mesh.faces.is_empty());
+    }
+}
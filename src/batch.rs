@@ -0,0 +1,253 @@
+use crate::csv_file::{self, csv_error, csv_field};
+use crate::error::{Error, Result};
+use crate::pipeline::{self, PipelineConfig};
+use crate::plant_traits::PlantTraits;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::panic;
+use std::path::{Path, PathBuf};
+
+// result of one file (or of one plant of the file with clustering), either the traits of the
+// plant or the error that stopped the pipeline.
+#[derive(Clone, Debug, Serialize)]
+pub struct BatchRow {
+    pub path: String,
+    // index of the plant in the file, with clustering
+    pub cluster: Option<usize>,
+    #[serde(flatten)]
+    pub traits: Option<PlantTraits>,
+    pub error: Option<String>,
+}
+
+// the PLY files of a directory (not recursive), or the files matching a glob pattern.
+// the files are sorted by path.
+pub fn find_ply_files(input: &str) -> Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    if Path::new(input).is_dir() {
+        let entries = match std::fs::read_dir(input) {
+            Ok(val) => val,
+            Err(err) => return Err(Error::Io(String::from(input), err)),
+        };
+        for entry in entries {
+            let path = match entry {
+                Ok(val) => val.path(),
+                Err(err) => return Err(Error::Io(String::from(input), err)),
+            };
+            let is_ply = path
+                .extension()
+                .map(|ext| ext.eq_ignore_ascii_case("ply"))
+                .unwrap_or(false);
+            if is_ply && path.is_file() {
+                paths.push(path);
+            }
+        }
+    } else {
+        let matches = match glob::glob(input) {
+            Ok(val) => val,
+            Err(err) => {
+                return Err(Error::InvalidArgument(format!(
+                    "invalid pattern {}, {}",
+                    input, err
+                )))
+            }
+        };
+        for entry in matches {
+            match entry {
+                Ok(path) => {
+                    if path.is_file() {
+                        paths.push(path);
+                    }
+                }
+                Err(err) => {
+                    let path = err.path().display().to_string();
+                    return Err(Error::Io(path, err.into()));
+                }
+            }
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+// run the pipeline on every file in parallel (on the current rayon pool).
+// the rows are in the same order as the paths, a failed file does not stop the others.
+pub fn process_files(paths: &[PathBuf], config: &PipelineConfig) -> Vec<BatchRow> {
+    paths
+        .par_iter()
+        .flat_map_iter(|path| {
+            let display = path.display().to_string();
+            let row = |cluster, traits, error| BatchRow {
+                path: display.clone(),
+                cluster,
+                traits,
+                error,
+            };
+            // a panic in one file must not abort the whole batch
+            let result = panic::catch_unwind(|| pipeline::process_file(path, config, &mut ()));
+            match result {
+                Ok(Ok(plants)) => {
+                    let clustered = config.clusters.is_some();
+                    plants
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, traits)| row(clustered.then_some(i), Some(traits), None))
+                        .collect()
+                }
+                Ok(Err(err)) => vec![row(None, None, Some(err.to_string()))],
+                Err(cause) => vec![row(None, None, Some(panic_message(cause)))],
+            }
+        })
+        .collect()
+}
+
+fn panic_message(cause: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(msg) = cause.downcast_ref::<&str>() {
+        format!("panicked, {}", msg)
+    } else if let Some(msg) = cause.downcast_ref::<String>() {
+        format!("panicked, {}", msg)
+    } else {
+        String::from("panicked")
+    }
+}
+
+const CSV_HEADER: &str =
+    "path,cluster,point_count,canopy_area,hull_surface_area,hull_volume,alpha_volume,max_height,error";
+
+// one row per file, the trait columns are empty for a failed file.
+pub fn write_csv(rows: &[BatchRow], path: &str) -> Result<()> {
+    let lines = rows.iter().map(|row| {
+        let traits = match &row.traits {
+            Some(t) => format!(
+                "{},{},{},{},{},{}",
+                t.point_count,
+                t.canopy_area,
+                t.hull_surface_area,
+                t.hull_volume,
+                t.alpha_volume.map(|v| v.to_string()).unwrap_or_default(),
+                t.max_height
+            ),
+            None => String::from(",,,,,"),
+        };
+        let cluster = row.cluster.map(|i| i.to_string()).unwrap_or_default();
+        format!(
+            "{},{},{},{}",
+            csv_field(&row.path),
+            cluster,
+            traits,
+            csv_error(row.error.as_deref())
+        )
+    });
+    csv_file::write_lines(path, CSV_HEADER, lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_ply_files, process_files, write_csv};
+    use crate::clustering::ClusterConfig;
+    use crate::geometry::Point;
+    use crate::pipeline::PipelineConfig;
+    use crate::ply_file::write_to_ply_file;
+    use std::path::PathBuf;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("batch-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // a box of 2 x 3 x 4 sitting on a thin layer of soil
+    fn plant_points() -> Vec<Point> {
+        let mut data = vec![];
+        for i in 0..5 {
+            for j in 0..5 {
+                data.push(Point::new(i as f64 * 0.5, j as f64 * 0.75, 0.0));
+            }
+        }
+        for x in [0.0, 2.0] {
+            for y in [0.0, 3.0] {
+                for z in [6.0, 10.0] {
+                    data.push(Point::new(x, y, z));
+                }
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn test_batch_failures_are_per_file() {
+        let dir = test_dir("files");
+        let plant = dir.join("a.ply");
+        write_to_ply_file(plant.display().to_string(), &plant_points()).unwrap();
+        std::fs::write(dir.join("b.ply"), "not a ply file").unwrap();
+        std::fs::write(dir.join("c.txt"), "ignored").unwrap();
+
+        let paths = find_ply_files(dir.to_str().unwrap()).unwrap();
+        assert_eq!(paths.len(), 2);
+        let pattern = format!("{}/*.ply", dir.display());
+        assert_eq!(find_ply_files(&pattern).unwrap(), paths);
+
+        let rows = process_files(&paths, &PipelineConfig::default());
+        assert_eq!(rows.len(), 2);
+        let traits = rows[0].traits.unwrap();
+        assert!(rows[0].error.is_none());
+        assert_eq!(traits.point_count, 8);
+        assert!((traits.canopy_area - 6.0).abs() < 0.000001);
+        assert!((traits.hull_volume - 24.0).abs() < 0.000001);
+        assert!((traits.max_height - 4.0).abs() < 0.000001);
+        assert!(rows[1].traits.is_none());
+        assert!(rows[1].error.is_some());
+
+        let csv = dir.join("traits.csv");
+        write_csv(&rows, csv.to_str().unwrap()).unwrap();
+        let content = std::fs::read_to_string(&csv).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].ends_with(','));
+        assert!(lines[2].contains(",,,,,"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_batch_clusters() {
+        let dir = test_dir("clusters");
+        // the same plant twice, 10 apart along x
+        let mut data = plant_points();
+        data.extend(
+            plant_points()
+                .iter()
+                .map(|pt| Point::new(pt.x + 10.0, pt.y, pt.z)),
+        );
+        let paths = vec![dir.join("a.ply")];
+        write_to_ply_file(paths[0].display().to_string(), &data).unwrap();
+
+        let config = PipelineConfig {
+            clusters: Some(ClusterConfig {
+                tolerance: 5.0,
+                min_points: 4,
+            }),
+            ..PipelineConfig::default()
+        };
+        let rows = process_files(&paths, &config);
+        assert_eq!(rows.len(), 2);
+        for (i, row) in rows.iter().enumerate() {
+            assert_eq!(row.cluster, Some(i));
+            let traits = row.traits.unwrap();
+            assert_eq!(traits.point_count, 8);
+            assert!((traits.hull_volume - 24.0).abs() < 0.000001);
+        }
+
+        // every cluster is too small
+        let config = PipelineConfig {
+            clusters: Some(ClusterConfig {
+                tolerance: 5.0,
+                min_points: 100,
+            }),
+            ..PipelineConfig::default()
+        };
+        let rows = process_files(&paths, &config);
+        assert_eq!(rows.len(), 1);
+        assert!(rows[0].error.is_some());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,891 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use final_project::clustering::ClusterConfig;
+use final_project::evaluation::{self, DEFAULT_SOIL_LABEL};
+use final_project::filters::{
+    OutlierConfig, RadiusFilter, StatisticalFilter, VoxelGrid, VoxelMode,
+};
+use final_project::soil_removal::{
+    BandConfig, ColorConfig, ExgThreshold, RansacConfig, SoilRemovalConfig, SoilRemovalMode,
+    DEFAULT_BUCKET_COUNT, DEFAULT_SOIL_BUCKETS,
+};
+use final_project::{convex_hull_2d, convex_hull_3d};
+
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Convex hulls and phenotyping traits of plant point clouds"
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    #[command(about = "2D convex hull of the points, on the x-y plane")]
+    Hull2d {
+        #[arg(help = "input PLY file")]
+        input: String,
+        #[arg(short, long, default_value = "hull2d.ply", help = "output PLY file")]
+        output: String,
+        #[command(flatten)]
+        hull: Hull2dArgs,
+    },
+    #[command(about = "3D convex hull of the points, written as a PLY mesh")]
+    Hull3d {
+        #[arg(help = "input PLY file")]
+        input: String,
+        #[arg(short, long, default_value = "hull3d.ply", help = "output PLY file")]
+        output: String,
+        #[command(flatten)]
+        hull: Hull3dArgs,
+    },
+    #[command(about = "Delaunay triangulation of the points on the x-y plane, as a PLY mesh")]
+    Delaunay {
+        #[arg(help = "input PLY file")]
+        input: String,
+        #[arg(short, long, default_value = "delaunay.ply", help = "output PLY file")]
+        output: String,
+        #[arg(long, help = "write the bounded Voronoi cells to this PLY file")]
+        voronoi: Option<String>,
+    },
+    #[command(about = "remove the soil points below the plant")]
+    Soil {
+        #[arg(help = "input PLY file")]
+        input: String,
+        #[arg(
+            short,
+            long,
+            default_value = "soil_removed.ply",
+            help = "output PLY file"
+        )]
+        output: String,
+        #[command(flatten)]
+        soil: SoilArgs,
+    },
+    #[command(about = "remove the soil, compute both hulls and the plant traits")]
+    Traits {
+        #[arg(help = "input PLY file")]
+        input: String,
+        #[command(flatten)]
+        soil: SoilArgs,
+        #[command(flatten)]
+        outliers: OutlierArgs,
+        #[command(flatten)]
+        voxel: VoxelArgs,
+        #[command(flatten)]
+        clusters: ClusterArgs,
+        #[command(flatten)]
+        hull_2d: Hull2dArgs,
+        #[command(flatten)]
+        hull_3d: Hull3dArgs,
+        #[arg(long, help = "write the points after soil removal to this PLY file")]
+        soil_output: Option<String>,
+        #[arg(
+            long,
+            help = "write the points of the plant to this PLY file, one file per plant with clustering"
+        )]
+        plant_output: Option<String>,
+        #[arg(long, help = "write the 2D hull to this PLY file")]
+        hull2d_output: Option<String>,
+        #[arg(long, help = "write the 3D hull to this PLY file")]
+        hull3d_output: Option<String>,
+        #[arg(
+            long,
+            requires = "alpha",
+            help = "write the alpha shape to this PLY file"
+        )]
+        alpha_output: Option<String>,
+        #[arg(
+            long,
+            default_value = "traits.json",
+            help = "output JSON file of the traits"
+        )]
+        json: String,
+        #[arg(
+            long,
+            default_value = "input.json",
+            help = "output JSON file of the input points"
+        )]
+        input_json: String,
+        #[arg(long, help = "do not write the JSON files")]
+        no_json: bool,
+    },
+    #[command(about = "compute the plant traits of many PLY files in parallel")]
+    Batch {
+        #[arg(help = "directory of PLY files, or a glob pattern such as 'scans/**/*.ply'")]
+        input: String,
+        #[arg(
+            short,
+            long,
+            default_value = "traits.csv",
+            help = "output file, JSON if it ends with .json, CSV otherwise"
+        )]
+        output: String,
+        #[command(flatten)]
+        soil: SoilArgs,
+        #[command(flatten)]
+        outliers: OutlierArgs,
+        #[command(flatten)]
+        voxel: VoxelArgs,
+        #[command(flatten)]
+        clusters: ClusterArgs,
+        #[command(flatten)]
+        hull_2d: Hull2dArgs,
+        #[command(flatten)]
+        hull_3d: Hull3dArgs,
+        #[arg(long, help = "number of threads [default: number of CPUs]")]
+        threads: Option<usize>,
+    },
+    #[command(about = "compare soil removal methods against hand-labelled PLY files")]
+    Compare {
+        #[arg(
+            help = "directory of labelled PLY files, or a glob pattern such as 'labelled/*.ply'"
+        )]
+        input: String,
+        #[arg(
+            short,
+            long,
+            help = "write the metrics of every file and method to this CSV file"
+        )]
+        output: Option<String>,
+        #[arg(
+            long,
+            value_enum,
+            value_delimiter = ',',
+            default_values_t = [
+                SoilMethod::Buckets, SoilMethod::Otsu, SoilMethod::Band, SoilMethod::Ransac
+            ],
+            help = "soil removal methods to compare, with their default parameters"
+        )]
+        methods: Vec<SoilMethod>,
+        #[arg(
+            long,
+            default_value_t = DEFAULT_SOIL_LABEL,
+            allow_hyphen_values = true,
+            help = "value of the label property of the soil points, any other value is plant"
+        )]
+        soil_label: f64,
+        #[arg(long, help = "number of threads [default: number of CPUs]")]
+        threads: Option<usize>,
+    },
+    #[command(about = "downsample the points on a voxel grid and report the change of the hulls")]
+    Downsample {
+        #[arg(help = "input PLY file")]
+        input: String,
+        #[arg(
+            short,
+            long,
+            default_value = "downsampled.ply",
+            help = "output PLY file"
+        )]
+        output: String,
+        #[arg(long, help = "edge of the voxels")]
+        leaf_size: f64,
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = VoxelModeArg::Extremes,
+            help = "points kept per voxel"
+        )]
+        voxel_mode: VoxelModeArg,
+        #[arg(long, help = "do not compute the hulls before and after")]
+        no_report: bool,
+    },
+    #[command(about = "convert the vertices of a PLY file of any scalar type to double x, y, z")]
+    Convert {
+        #[arg(help = "input PLY file")]
+        input: String,
+        #[arg(help = "output PLY file")]
+        output: String,
+    },
+    #[command(about = "time a 2D convex hull algorithm on the points")]
+    Bench {
+        #[arg(help = "input PLY file")]
+        input: String,
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = Hull2dAlgorithm::Chan,
+            help = "2D convex hull algorithm"
+        )]
+        algorithm: Hull2dAlgorithm,
+        #[arg(long, default_value_t = 3, help = "number of runs")]
+        runs: usize,
+    },
+}
+
+#[derive(Args)]
+pub struct Hull2dArgs {
+    #[arg(
+        long = "algorithm",
+        value_enum,
+        default_value_t = Hull2dAlgorithm::Chan,
+        help = "2D convex hull algorithm"
+    )]
+    pub algorithm: Hull2dAlgorithm,
+    #[arg(
+        long,
+        help = "use a concave hull of the k nearest neighbors instead, a smaller k is tighter"
+    )]
+    pub concave_k: Option<usize>,
+}
+
+#[derive(Args)]
+pub struct Hull3dArgs {
+    #[arg(
+        id = "algorithm_3d",
+        long = "algorithm-3d",
+        value_name = "ALGORITHM",
+        value_enum,
+        default_value_t = Hull3dAlgorithm::Quickhull,
+        help = "3D convex hull algorithm"
+    )]
+    pub algorithm: Hull3dAlgorithm,
+    #[arg(
+        long,
+        help = "also compute the alpha shape of this circumsphere radius, a tighter volume"
+    )]
+    pub alpha: Option<f64>,
+}
+
+// the soil cut is one of --soil-buckets (the default), --soil-percentile, --soil-height,
+// --soil-otsu, --soil-ransac and --soil-band. --soil-color removes the soil by color instead, and
+// with --with-height it keeps the points above the height cut of the other options, and removes
+// the green points of the lowest bucket.
+#[derive(Args)]
+pub struct SoilArgs {
+    #[arg(long, default_value_t = DEFAULT_BUCKET_COUNT, help = "number of buckets along z")]
+    pub bucket_count: usize,
+    #[arg(
+        long,
+        help = "points in the buckets up to this one (from the bottom) are soil [default: 43]"
+    )]
+    pub soil_buckets: Option<usize>,
+    #[arg(
+        long,
+        conflicts_with_all = ["soil_buckets", "soil_height", "soil_otsu"],
+        help = "points below this percentile (0 - 100) of z are soil"
+    )]
+    pub soil_percentile: Option<f64>,
+    #[arg(
+        long,
+        allow_hyphen_values = true,
+        conflicts_with_all = ["soil_buckets", "soil_otsu"],
+        help = "points at or below this z are soil"
+    )]
+    pub soil_height: Option<f64>,
+    #[arg(
+        long,
+        conflicts_with = "soil_buckets",
+        help = "cut the soil at the valley of the z histogram (Otsu's method)"
+    )]
+    pub soil_otsu: bool,
+    #[arg(
+        long,
+        conflicts_with_all = ["soil_buckets", "soil_percentile", "soil_height", "soil_otsu"],
+        help = "remove the ground plane fitted with RANSAC, for sloped beds"
+    )]
+    pub soil_ransac: bool,
+    #[arg(
+        long,
+        requires = "soil_ransac",
+        help = "points closer to the ground plane than this are soil [default: 0.01]"
+    )]
+    pub ransac_tolerance: Option<f64>,
+    #[arg(
+        long,
+        requires = "soil_ransac",
+        help = "number of random planes tried [default: 1000]"
+    )]
+    pub ransac_iterations: Option<usize>,
+    #[arg(
+        long,
+        requires = "soil_ransac",
+        help = "max angle in degrees between the ground plane and the x-y plane [default: 30]"
+    )]
+    pub ransac_max_tilt: Option<f64>,
+    #[arg(
+        long,
+        requires = "soil_ransac",
+        help = "rotate the plant so that the ground plane is z = 0"
+    )]
+    pub level: bool,
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "soil_buckets", "soil_percentile", "soil_height", "soil_otsu", "soil_ransac"
+        ],
+        help = "keep the points between the steep rise and fall of the ground along x"
+    )]
+    pub soil_band: bool,
+    #[arg(
+        long,
+        requires = "soil_band",
+        help = "number of bands along y [default: 100]"
+    )]
+    pub band_count: Option<usize>,
+    #[arg(
+        long,
+        requires = "soil_band",
+        help = "the plant starts where dz / dx is above this [default: 1]"
+    )]
+    pub rise_slope: Option<f64>,
+    #[arg(
+        long,
+        requires = "soil_band",
+        help = "the plant ends after dz / dx went below minus this [default: 1]"
+    )]
+    pub fall_slope: Option<f64>,
+    #[arg(
+        long,
+        conflicts_with_all = ["soil_ransac", "soil_band"],
+        help = "remove the points that are not green (excess green index), the PLY needs colors"
+    )]
+    pub soil_color: bool,
+    #[arg(
+        long,
+        allow_hyphen_values = true,
+        requires = "soil_color",
+        help = "points with an excess green index above this are plant [default: k-means]"
+    )]
+    pub exg_threshold: Option<f64>,
+    #[arg(
+        long,
+        requires = "soil_color",
+        help = "also keep the points above the height cut (e.g. brown leaves) and remove the green \
+                points of the lowest bucket (e.g. green tinted soil)"
+    )]
+    pub with_height: bool,
+}
+
+impl SoilArgs {
+    // None unless the soil is removed by color.
+    pub fn color_config(&self) -> Option<ColorConfig> {
+        if !self.soil_color {
+            return None;
+        }
+        let threshold = match self.exg_threshold {
+            Some(t) => ExgThreshold::Fixed(t),
+            None => ExgThreshold::KMeans,
+        };
+        let height = if self.with_height {
+            Some(self.config())
+        } else {
+            None
+        };
+        Some(ColorConfig { threshold, height })
+    }
+
+    pub fn config(&self) -> SoilRemovalConfig {
+        let mode = if let Some(p) = self.soil_percentile {
+            SoilRemovalMode::Percentile(p)
+        } else if let Some(z) = self.soil_height {
+            SoilRemovalMode::Height(z)
+        } else if self.soil_otsu {
+            SoilRemovalMode::Otsu
+        } else if self.soil_ransac {
+            let default = RansacConfig::default();
+            SoilRemovalMode::Ransac(RansacConfig {
+                iterations: self.ransac_iterations.unwrap_or(default.iterations),
+                distance_tolerance: self.ransac_tolerance.unwrap_or(default.distance_tolerance),
+                max_tilt_degrees: self.ransac_max_tilt.unwrap_or(default.max_tilt_degrees),
+                level: self.level,
+                seed: default.seed,
+            })
+        } else if self.soil_band {
+            let default = BandConfig::default();
+            SoilRemovalMode::Band(BandConfig {
+                band_count: self.band_count.unwrap_or(default.band_count),
+                window: default.window,
+                rise_slope: self.rise_slope.unwrap_or(default.rise_slope),
+                fall_slope: self.fall_slope.unwrap_or(default.fall_slope),
+            })
+        } else {
+            SoilRemovalMode::Buckets {
+                soil_buckets: self.soil_buckets.unwrap_or(DEFAULT_SOIL_BUCKETS),
+            }
+        };
+        SoilRemovalConfig {
+            mode,
+            bucket_count: self.bucket_count,
+        }
+    }
+}
+
+// outlier removal between the soil removal and the hulls, off by default.
+#[derive(Args)]
+pub struct OutlierArgs {
+    #[arg(
+        long,
+        help = "remove the points far from their neighbors (statistical outlier removal)"
+    )]
+    pub sor: bool,
+    #[arg(
+        long,
+        requires = "sor",
+        help = "number of neighbors the mean distance is measured over [default: 20]"
+    )]
+    pub sor_neighbors: Option<usize>,
+    #[arg(
+        long,
+        requires = "sor",
+        help = "cutoff in standard deviations above the mean of the mean distances [default: 2]"
+    )]
+    pub sor_std_ratio: Option<f64>,
+    #[arg(
+        long,
+        value_name = "RADIUS",
+        help = "remove the points with too few neighbors within this radius"
+    )]
+    pub radius_filter: Option<f64>,
+    #[arg(
+        long,
+        default_value_t = 2,
+        requires = "radius_filter",
+        help = "neighbors needed within the radius"
+    )]
+    pub min_neighbors: usize,
+}
+
+impl OutlierArgs {
+    pub fn config(&self) -> OutlierConfig {
+        let default = StatisticalFilter::default();
+        let statistical = if self.sor {
+            Some(StatisticalFilter {
+                k: self.sor_neighbors.unwrap_or(default.k),
+                std_ratio: self.sor_std_ratio.unwrap_or(default.std_ratio),
+            })
+        } else {
+            None
+        };
+        let radius = self.radius_filter.map(|radius| RadiusFilter {
+            radius,
+            min_neighbors: self.min_neighbors,
+        });
+        OutlierConfig {
+            statistical,
+            radius,
+        }
+    }
+}
+
+// voxel downsampling after the outlier removal, off by default.
+#[derive(Args)]
+pub struct VoxelArgs {
+    #[arg(
+        long,
+        help = "downsample the plant on voxels of this size before the hulls"
+    )]
+    pub voxel_size: Option<f64>,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = VoxelModeArg::Extremes,
+        requires = "voxel_size",
+        help = "points kept per voxel"
+    )]
+    pub voxel_mode: VoxelModeArg,
+}
+
+impl VoxelArgs {
+    pub fn grid(&self) -> Option<VoxelGrid> {
+        self.voxel_size.map(|leaf_size| VoxelGrid {
+            leaf_size,
+            mode: self.voxel_mode.mode(),
+        })
+    }
+}
+
+// split the plant into plants after the downsampling, off by default. with clustering, the
+// hulls and traits are per plant, and every output file gets the index of the plant.
+#[derive(Args)]
+pub struct ClusterArgs {
+    #[arg(
+        long,
+        help = "split the points into plants, points closer than this are in the same plant"
+    )]
+    pub cluster_tolerance: Option<f64>,
+    #[arg(
+        long,
+        default_value_t = 100,
+        requires = "cluster_tolerance",
+        help = "plants of fewer points are dropped"
+    )]
+    pub min_cluster_size: usize,
+}
+
+impl ClusterArgs {
+    pub fn config(&self) -> Option<ClusterConfig> {
+        self.cluster_tolerance.map(|tolerance| ClusterConfig {
+            tolerance,
+            min_points: self.min_cluster_size,
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum VoxelModeArg {
+    // the centroid of the voxel
+    Centroid,
+    // the extreme points of the voxel, keeps the hull
+    Extremes,
+}
+
+impl VoxelModeArg {
+    pub fn mode(self) -> VoxelMode {
+        match self {
+            VoxelModeArg::Centroid => VoxelMode::Centroid,
+            VoxelModeArg::Extremes => VoxelMode::Extremes,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum SoilMethod {
+    Buckets,
+    Otsu,
+    Band,
+    Ransac,
+    Color,
+}
+
+impl SoilMethod {
+    pub fn method(self) -> evaluation::Method {
+        let soil = |mode| {
+            evaluation::Method::Soil(SoilRemovalConfig {
+                mode,
+                ..SoilRemovalConfig::default()
+            })
+        };
+        match self {
+            SoilMethod::Buckets => soil(SoilRemovalConfig::default().mode),
+            SoilMethod::Otsu => soil(SoilRemovalMode::Otsu),
+            SoilMethod::Band => soil(SoilRemovalMode::Band(BandConfig::default())),
+            SoilMethod::Ransac => soil(SoilRemovalMode::Ransac(RansacConfig::default())),
+            SoilMethod::Color => evaluation::Method::Color(ColorConfig::default()),
+        }
+    }
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+pub enum Hull2dAlgorithm {
+    Andrew,
+    Jarvis,
+    Chan,
+    ChanThreaded,
+}
+
+impl Hull2dAlgorithm {
+    pub fn algorithm(self) -> convex_hull_2d::Algorithm {
+        match self {
+            Hull2dAlgorithm::Andrew => convex_hull_2d::Algorithm::Andrew,
+            Hull2dAlgorithm::Jarvis => convex_hull_2d::Algorithm::JarvisMarch,
+            Hull2dAlgorithm::Chan => convex_hull_2d::Algorithm::Chan,
+            Hull2dAlgorithm::ChanThreaded => convex_hull_2d::Algorithm::ChanThreaded,
+        }
+    }
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+pub enum Hull3dAlgorithm {
+    Quickhull,
+    Jarvis,
+    Chan,
+    Delaunay,
+}
+
+impl Hull3dAlgorithm {
+    pub fn algorithm(self) -> convex_hull_3d::Algorithm {
+        match self {
+            Hull3dAlgorithm::Quickhull => convex_hull_3d::Algorithm::QuickHull,
+            Hull3dAlgorithm::Jarvis => convex_hull_3d::Algorithm::JarvisMarch,
+            Hull3dAlgorithm::Chan => convex_hull_3d::Algorithm::Chan,
+            Hull3dAlgorithm::Delaunay => convex_hull_3d::Algorithm::Delaunay,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cli, Command, Hull2dAlgorithm, SoilMethod};
+    use clap::{CommandFactory, Parser};
+    use final_project::filters::{OutlierConfig, VoxelGrid, VoxelMode};
+    use final_project::soil_removal::{ColorConfig, ExgThreshold, SoilRemovalMode};
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_cli_bench() {
+        let cli = Cli::try_parse_from(["final_project", "bench", "a.ply", "--algorithm", "andrew"])
+            .unwrap();
+        match cli.command {
+            Command::Bench {
+                algorithm, runs, ..
+            } => {
+                assert!(matches!(algorithm, Hull2dAlgorithm::Andrew));
+                assert_eq!(runs, 3);
+            }
+            _ => panic!("expected the bench command"),
+        }
+        // only the convex hull algorithms are timed
+        assert!(
+            Cli::try_parse_from(["final_project", "bench", "a.ply", "--concave-k", "10"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_parse_traits() {
+        let cli = Cli::try_parse_from([
+            "final_project",
+            "traits",
+            "plant.ply",
+            "--algorithm",
+            "chan-threaded",
+            "--soil-buckets",
+            "30",
+            "--no-json",
+        ])
+        .unwrap();
+        match cli.command {
+            Command::Traits {
+                input,
+                soil,
+                hull_2d,
+                hull3d_output,
+                no_json,
+                ..
+            } => {
+                assert_eq!(input, "plant.ply");
+                assert_eq!(soil.bucket_count, 100);
+                assert_eq!(
+                    soil.config().mode,
+                    SoilRemovalMode::Buckets { soil_buckets: 30 }
+                );
+                assert!(matches!(hull_2d.algorithm, Hull2dAlgorithm::ChanThreaded));
+                assert_eq!(hull_2d.concave_k, None);
+                assert_eq!(hull3d_output, None);
+                assert!(no_json);
+            }
+            _ => panic!("expected the traits command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_soil_modes() {
+        let soil_mode = |args: &[&str]| {
+            let cli = Cli::try_parse_from(["final_project", "soil", "a.ply"].iter().chain(args))?;
+            match cli.command {
+                Command::Soil { soil, .. } => Ok::<_, clap::Error>(soil.config().mode),
+                _ => panic!("expected the soil command"),
+            }
+        };
+        assert_eq!(
+            soil_mode(&[]).unwrap(),
+            SoilRemovalMode::Buckets { soil_buckets: 43 }
+        );
+        assert_eq!(
+            soil_mode(&["--soil-percentile", "20"]).unwrap(),
+            SoilRemovalMode::Percentile(20.0)
+        );
+        assert_eq!(
+            soil_mode(&["--soil-height", "-1.5"]).unwrap(),
+            SoilRemovalMode::Height(-1.5)
+        );
+        assert_eq!(
+            soil_mode(&["--soil-otsu", "--bucket-count", "50"]).unwrap(),
+            SoilRemovalMode::Otsu
+        );
+        assert!(soil_mode(&["--soil-otsu", "--soil-height", "1"]).is_err());
+        match soil_mode(&["--soil-ransac", "--ransac-tolerance", "0.05", "--level"]).unwrap() {
+            SoilRemovalMode::Ransac(ransac) => {
+                assert_eq!(ransac.distance_tolerance, 0.05);
+                assert_eq!(ransac.iterations, 1000);
+                assert!(ransac.level);
+            }
+            mode => panic!("unexpected mode {:?}", mode),
+        }
+        assert!(soil_mode(&["--level"]).is_err());
+        match soil_mode(&["--soil-band", "--band-count", "40", "--rise-slope", "2"]).unwrap() {
+            SoilRemovalMode::Band(band) => {
+                assert_eq!(band.band_count, 40);
+                assert_eq!(band.rise_slope, 2.0);
+                assert_eq!(band.fall_slope, 1.0);
+            }
+            mode => panic!("unexpected mode {:?}", mode),
+        }
+        assert!(soil_mode(&["--soil-band", "--soil-otsu"]).is_err());
+        assert!(soil_mode(&["--rise-slope", "2"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_soil_color() {
+        let color = |args: &[&str]| {
+            let cli = Cli::try_parse_from(["final_project", "soil", "a.ply"].iter().chain(args))?;
+            match cli.command {
+                Command::Soil { soil, .. } => Ok::<_, clap::Error>(soil.color_config()),
+                _ => panic!("expected the soil command"),
+            }
+        };
+        assert_eq!(color(&[]).unwrap(), None);
+        assert_eq!(
+            color(&["--soil-color"]).unwrap(),
+            Some(ColorConfig::default())
+        );
+        let config = color(&[
+            "--soil-color",
+            "--exg-threshold",
+            "-0.1",
+            "--with-height",
+            "--soil-height",
+            "2",
+        ])
+        .unwrap()
+        .unwrap();
+        assert_eq!(config.threshold, ExgThreshold::Fixed(-0.1));
+        assert_eq!(config.height.unwrap().mode, SoilRemovalMode::Height(2.0));
+        assert!(color(&["--soil-color", "--soil-ransac"]).is_err());
+        assert!(color(&["--with-height"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_outliers() {
+        let outliers = |args: &[&str]| {
+            let cli = Cli::try_parse_from(["final_project", "traits", "a.ply"].iter().chain(args))?;
+            match cli.command {
+                Command::Traits { outliers, .. } => Ok::<_, clap::Error>(outliers.config()),
+                _ => panic!("expected the traits command"),
+            }
+        };
+        assert_eq!(outliers(&[]).unwrap(), OutlierConfig::default());
+        let config =
+            outliers(&["--sor", "--sor-neighbors", "8", "--radius-filter", "0.01"]).unwrap();
+        assert_eq!(config.statistical.unwrap().k, 8);
+        assert_eq!(config.statistical.unwrap().std_ratio, 2.0);
+        assert_eq!(config.radius.unwrap().min_neighbors, 2);
+        assert!(outliers(&["--sor-neighbors", "8"]).is_err());
+        assert!(outliers(&["--min-neighbors", "3"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_voxel() {
+        let grid = |args: &[&str]| {
+            let cli = Cli::try_parse_from(["final_project", "batch", "scans"].iter().chain(args))?;
+            match cli.command {
+                Command::Batch { voxel, .. } => Ok::<_, clap::Error>(voxel.grid()),
+                _ => panic!("expected the batch command"),
+            }
+        };
+        assert_eq!(grid(&[]).unwrap(), None);
+        let voxel = grid(&["--voxel-size", "0.002", "--voxel-mode", "centroid"]).unwrap();
+        assert_eq!(
+            voxel,
+            Some(VoxelGrid {
+                leaf_size: 0.002,
+                mode: VoxelMode::Centroid
+            })
+        );
+        assert!(grid(&["--voxel-mode", "centroid"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_clusters() {
+        let cli = Cli::try_parse_from([
+            "final_project",
+            "traits",
+            "a.ply",
+            "--cluster-tolerance",
+            "0.02",
+            "--plant-output",
+            "plant.ply",
+        ])
+        .unwrap();
+        match cli.command {
+            Command::Traits {
+                clusters,
+                plant_output,
+                ..
+            } => {
+                let config = clusters.config().unwrap();
+                assert_eq!(config.tolerance, 0.02);
+                assert_eq!(config.min_points, 100);
+                assert_eq!(plant_output.as_deref(), Some("plant.ply"));
+            }
+            _ => panic!("expected the traits command"),
+        }
+        // without clustering, the points of the one plant
+        let cli = Cli::try_parse_from([
+            "final_project",
+            "traits",
+            "a.ply",
+            "--plant-output",
+            "p.ply",
+        ])
+        .unwrap();
+        match cli.command {
+            Command::Traits {
+                clusters,
+                plant_output,
+                ..
+            } => {
+                assert!(clusters.config().is_none());
+                assert_eq!(plant_output.as_deref(), Some("p.ply"));
+            }
+            _ => panic!("expected the traits command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_compare_methods() {
+        let methods = |args: &[&str]| {
+            let cli =
+                Cli::try_parse_from(["final_project", "compare", "labelled"].iter().chain(args))?;
+            match cli.command {
+                Command::Compare { methods, .. } => Ok::<_, clap::Error>(methods),
+                _ => panic!("expected the compare command"),
+            }
+        };
+        assert_eq!(methods(&[]).unwrap().len(), 4);
+        assert_eq!(
+            methods(&["--methods", "band,color"]).unwrap(),
+            vec![SoilMethod::Band, SoilMethod::Color]
+        );
+        assert_eq!(SoilMethod::Color.method().name(), "color");
+        assert!(methods(&["--methods", "x"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_unknown_algorithm() {
+        assert!(
+            Cli::try_parse_from(["final_project", "hull2d", "a.ply", "--algorithm", "x"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_delaunay() {
+        let cli = Cli::try_parse_from(["final_project", "delaunay", "a.ply", "--voronoi", "v.ply"])
+            .unwrap();
+        match cli.command {
+            Command::Delaunay {
+                output, voronoi, ..
+            } => {
+                assert_eq!(output, "delaunay.ply");
+                assert_eq!(voronoi.as_deref(), Some("v.ply"));
+            }
+            _ => panic!("expected the delaunay command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_concave_k() {
+        let cli =
+            Cli::try_parse_from(["final_project", "hull2d", "a.ply", "--concave-k", "12"]).unwrap();
+        match cli.command {
+            Command::Hull2d { hull, .. } => assert_eq!(hull.concave_k, Some(12)),
+            _ => panic!("expected the hull2d command"),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use crate::error::{Error, Result};
+use crate::geometry::Point;
+use crate::kdtree::KdTree;
+
+// euclidean cluster extraction: 2 points closer than tolerance are in the same cluster, and the
+// clusters of fewer than min_points points are dropped as noise.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ClusterConfig {
+    pub tolerance: f64,
+    pub min_points: usize,
+}
+
+impl ClusterConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !(self.tolerance > 0.0 && self.tolerance.is_finite()) {
+            return Err(Error::InvalidArgument(format!(
+                "cluster tolerance {} must be positive",
+                self.tolerance
+            )));
+        }
+        Ok(())
+    }
+}
+
+// split the points into clusters, the largest first. the points of a cluster keep their order.
+pub fn euclidean_clusters(points: &[Point], config: &ClusterConfig) -> Result<Vec<Vec<Point>>> {
+    config.validate()?;
+    let tree = KdTree::new(points);
+    let mut visited = vec![false; points.len()];
+    let mut clusters: Vec<Vec<usize>> = vec![];
+    for seed in 0..points.len() {
+        if visited[seed] {
+            continue;
+        }
+        visited[seed] = true;
+        // grow the cluster from the seed, breadth first
+        let mut cluster = vec![seed];
+        let mut next = 0;
+        while next < cluster.len() {
+            let pt = &points[cluster[next]];
+            next += 1;
+            for neighbor in tree.within_radius(pt, config.tolerance) {
+                if !visited[neighbor.index] {
+                    visited[neighbor.index] = true;
+                    cluster.push(neighbor.index);
+                }
+            }
+        }
+        if cluster.len() >= config.min_points {
+            cluster.sort_unstable();
+            clusters.push(cluster);
+        }
+    }
+    // stable, the clusters of the same size stay in the order of their first point
+    clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.len()));
+    Ok(clusters
+        .iter()
+        .map(|cluster| cluster.iter().map(|i| points[*i]).collect())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{euclidean_clusters, ClusterConfig};
+    use crate::geometry::Point;
+
+    // a cube of size^3 points of spacing 0.1, with its corner at (x, y, 0)
+    fn blob(x: f64, y: f64, size: usize) -> Vec<Point> {
+        let mut data = vec![];
+        for i in 0..size {
+            for j in 0..size {
+                for k in 0..size {
+                    data.push(Point::new(
+                        x + i as f64 * 0.1,
+                        y + j as f64 * 0.1,
+                        k as f64 * 0.1,
+                    ));
+                }
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn test_euclidean_clusters() {
+        // 3 plants, the last one smaller, and a stray point
+        let mut data = blob(0.0, 0.0, 5);
+        data.extend(blob(2.0, 0.0, 4));
+        data.extend(blob(0.0, 2.0, 5));
+        data.push(Point::new(5.0, 5.0, 5.0));
+        let config = ClusterConfig {
+            tolerance: 0.15,
+            min_points: 10,
+        };
+        let clusters = euclidean_clusters(&data, &config).unwrap();
+        assert_eq!(clusters.len(), 3);
+        assert_eq!(clusters[0].len(), 125);
+        assert_eq!(clusters[1].len(), 125);
+        assert_eq!(clusters[2].len(), 64);
+        // the larger clusters in the order of their first point
+        assert!(clusters[0].iter().all(|pt| pt.y < 1.0 && pt.x < 1.0));
+        assert!(clusters[1].iter().all(|pt| pt.y > 1.0));
+        assert!(clusters[2].iter().all(|pt| pt.x > 1.0));
+
+        // a tolerance larger than the gaps merges everything
+        let config = ClusterConfig {
+            tolerance: 10.0,
+            min_points: 1,
+        };
+        assert_eq!(euclidean_clusters(&data, &config).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_euclidean_clusters_chain() {
+        // a chain of points 0.1 apart is a single cluster even though its ends are far apart
+        let data: Vec<Point> = (0..50)
+            .map(|i| Point::new(i as f64 * 0.1, 0.0, 0.0))
+            .collect();
+        let config = ClusterConfig {
+            tolerance: 0.11,
+            min_points: 1,
+        };
+        let clusters = euclidean_clusters(&data, &config).unwrap();
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].len(), 50);
+    }
+
+    #[test]
+    fn test_euclidean_clusters_invalid() {
+        let config = ClusterConfig {
+            tolerance: -1.0,
+            min_points: 1,
+        };
+        assert!(euclidean_clusters(&blob(0.0, 0.0, 2), &config).is_err());
+        let config = ClusterConfig {
+            tolerance: 1.0,
+            min_points: 1,
+        };
+        assert!(euclidean_clusters(&[], &config).unwrap().is_empty());
+    }
+}
@@ -1,14 +1,12 @@
 use super::convex_hull::jarvis_march;
 use crate::geometry::Point;
 
-#[allow(dead_code)]
 // reorder the points before compute the 2D area (x-y plane).
 pub fn convex_hull_area_reorder(hull: &[Point]) -> f64 {
     let reordered = jarvis_march(hull);
     convex_hull_area(&reordered)
 }
 
-#[allow(dead_code)]
 // compute the 2D area (x-y plane).
 // Note: this assumes the vertices are ordered (clockwise or counter-clockwise).
 pub fn convex_hull_area(hull: &[Point]) -> f64 {
@@ -43,7 +41,7 @@ mod tests {
     use super::{convex_hull_area, convex_hull_area_reorder};
     use crate::geometry::Point;
 
-    struct test_data {
+    struct TestData {
         data: Vec<Point>,
         expected_area: f64,
         error: bool,
@@ -51,8 +49,8 @@ mod tests {
 
     #[test]
     fn test_convex_hull_area() {
-        let all_data: Vec<test_data> = vec![
-            test_data {
+        let all_data: Vec<TestData> = vec![
+            TestData {
                 data: vec![
                     Point::new(0.0, 0.0, 0.0),
                     Point::new(0.0, 5.0, 0.0),
@@ -61,7 +59,7 @@ mod tests {
                 expected_area: (3.0 * 5.0) / 2.0,
                 error: false,
             },
-            test_data {
+            TestData {
                 data: vec![
                     Point::new(0.0, 0.0, 0.0),
                     Point::new(6.6, 0.0, 0.0),
@@ -70,7 +68,7 @@ mod tests {
                 expected_area: (6.6 * 1.3) / 2.0,
                 error: false,
             },
-            test_data {
+            TestData {
                 data: vec![
                     Point::new(0.0, 0.0, 0.0),
                     Point::new(6.6, 0.0, 0.0),
@@ -80,7 +78,7 @@ mod tests {
                 expected_area: 6.6 * 3.3,
                 error: false,
             },
-            test_data {
+            TestData {
                 data: vec![
                     Point::new(0.0, 0.0, 0.0),
                     Point::new(6.6, 0.0, 0.0),
@@ -97,15 +95,15 @@ mod tests {
             let tolerance = 0.000001;
             if !case.error && (area - case.expected_area).abs() > tolerance {
                 eprintln!("area: {}, expected: {}", area, case.expected_area);
-                assert!(false);
+                panic!();
             }
         }
     }
 
     #[test]
     fn test_convex_hull_area_reorder() {
-        let all_data: Vec<test_data> = vec![
-            test_data {
+        let all_data: Vec<TestData> = vec![
+            TestData {
                 data: vec![
                     Point::new(0.0, 0.0, 0.0),
                     Point::new(6.6, 0.0, 0.0),
@@ -115,7 +113,7 @@ mod tests {
                 expected_area: 6.6 * 3.3,
                 error: false,
             },
-            test_data {
+            TestData {
                 data: vec![
                     Point::new(0.0, 0.0, 0.0),
                     Point::new(6.6, 0.0, 0.0),
@@ -131,7 +129,7 @@ mod tests {
             let tolerance = 0.000001;
             if !case.error && (area - case.expected_area).abs() > tolerance {
                 eprintln!("area: {}, expected: {}", area, case.expected_area);
-                assert!(false);
+                panic!();
             }
         }
     }
@@ -0,0 +1,283 @@
+use super::convex_hull::andrew_algo;
+use crate::error::{Error, Result};
+use crate::geometry::{sort_points_by_x, Point};
+use crate::kdtree::KdTree;
+use crate::predicates::orient2d;
+use std::f64::consts::PI;
+
+// k-nearest neighbors concave hull (x-y plane), after Moreira and Santos,
+// "Concave Hull: A k-nearest neighbours approach for the computation of the region occupied by a
+// set of points" (2007).
+// the outline is walked from the lowest point, each step goes to the neighbor (among the k
+// nearest points) with the sharpest right turn that does not cross the outline. if the walk gets
+// stuck, or some points are left outside, it starts over with k + 1. a smaller k follows the
+// points more closely, the convex hull is returned when k reaches the number of points.
+// the result is an ordered polygon in counter-clockwise order, z is kept.
+pub fn concave_hull(data: &[Point], k: usize) -> Result<Vec<Point>> {
+    if k == 0 {
+        return Err(Error::InvalidArgument(String::from(
+            "the concave hull needs at least 1 neighbor",
+        )));
+    }
+    let mut points = data.to_vec();
+    // sort by x, and remove the points at the same x-y
+    sort_points_by_x(&mut points);
+    points.dedup_by(|a, b| a.x == b.x && a.y == b.y);
+    if points.len() < 3 {
+        return Err(Error::NotEnoughPoints {
+            required: 3,
+            found: points.len(),
+        });
+    }
+    let flat: Vec<Point> = points
+        .iter()
+        .map(|pt| Point::new(pt.x, pt.y, 0.0))
+        .collect();
+    let tree = KdTree::new(&flat);
+
+    let mut k = k.max(3);
+    while k < points.len() {
+        if let Some(hull) = walk(&flat, &tree, k) {
+            if points.iter().all(|pt| inside_or_on(&hull, &flat, pt)) {
+                return Ok(hull.iter().map(|i| points[*i]).collect());
+            }
+        }
+        k += 1;
+    }
+    andrew_algo(&points).map(|mut hull| {
+        // andrew_algo is clockwise
+        hull.reverse();
+        hull
+    })
+}
+
+// the indices of the outline, None if the walk got stuck.
+fn walk(points: &[Point], tree: &KdTree, k: usize) -> Option<Vec<usize>> {
+    let first = (0..points.len())
+        .min_by(|a, b| {
+            let (a, b) = (&points[*a], &points[*b]);
+            a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x))
+        })
+        .unwrap();
+    let mut used = vec![false; points.len()];
+    used[first] = true;
+    let mut hull = vec![first];
+    let mut current = first;
+    // the walk starts along +x, the other points are above the first one
+    let mut direction: (f64, f64) = (1.0, 0.0);
+    loop {
+        // the first point can be reached again once the outline is a triangle
+        if hull.len() == 4 {
+            used[first] = false;
+        }
+        let mut candidates = nearest_unused(points, tree, &used, current, k);
+        // the sharpest right turn first, then the nearest
+        let turn = |i: &usize| {
+            let (dx, dy) = (
+                points[*i].x - points[current].x,
+                points[*i].y - points[current].y,
+            );
+            let angle = dy.atan2(dx) - direction.1.atan2(direction.0);
+            // in (-pi, pi], negative is a right turn
+            let mut angle = angle % (2.0 * PI);
+            if angle <= -PI {
+                angle += 2.0 * PI;
+            } else if angle > PI {
+                angle -= 2.0 * PI;
+            }
+            angle
+        };
+        candidates.sort_by(|a, b| turn(a).total_cmp(&turn(b)));
+        let next = candidates
+            .into_iter()
+            .find(|candidate| !crosses(points, &hull, current, *candidate, first))?;
+        if next == first {
+            return Some(hull);
+        }
+        used[next] = true;
+        direction = (
+            points[next].x - points[current].x,
+            points[next].y - points[current].y,
+        );
+        hull.push(next);
+        current = next;
+    }
+}
+
+// the k nearest points that are not on the outline yet.
+fn nearest_unused(
+    points: &[Point],
+    tree: &KdTree,
+    used: &[bool],
+    current: usize,
+    k: usize,
+) -> Vec<usize> {
+    let mut count = k + 1;
+    loop {
+        let result: Vec<usize> = tree
+            .nearest(&points[current], count)
+            .iter()
+            .map(|n| n.index)
+            .filter(|i| !used[*i] && *i != current)
+            .take(k)
+            .collect();
+        if result.len() == k || count >= points.len() {
+            return result;
+        }
+        count *= 2;
+    }
+}
+
+// whether the edge current -> candidate crosses an edge of the outline, the edges that share a
+// point with it are not checked.
+fn crosses(
+    points: &[Point],
+    hull: &[usize],
+    current: usize,
+    candidate: usize,
+    first: usize,
+) -> bool {
+    if hull.len() < 3 {
+        return false;
+    }
+    // the last edge ends at current, and the first edge starts at first when closing
+    let start = if candidate == first { 1 } else { 0 };
+    (start..hull.len() - 2).any(|i| {
+        segments_cross(
+            &points[current],
+            &points[candidate],
+            &points[hull[i]],
+            &points[hull[i + 1]],
+        )
+    })
+}
+
+// the segments cross or touch, shared end points are not checked by the caller.
+fn segments_cross(a: &Point, b: &Point, c: &Point, d: &Point) -> bool {
+    let abc = orient2d(a, b, c);
+    let abd = orient2d(a, b, d);
+    let cda = orient2d(c, d, a);
+    let cdb = orient2d(c, d, b);
+    if ((abc > 0.0 && abd < 0.0) || (abc < 0.0 && abd > 0.0))
+        && ((cda > 0.0 && cdb < 0.0) || (cda < 0.0 && cdb > 0.0))
+    {
+        return true;
+    }
+    (abc == 0.0 && on_segment(a, b, c))
+        || (abd == 0.0 && on_segment(a, b, d))
+        || (cda == 0.0 && on_segment(c, d, a))
+        || (cdb == 0.0 && on_segment(c, d, b))
+}
+
+// pt is in the bounding box of the segment a-b, for a collinear pt
+fn on_segment(a: &Point, b: &Point, pt: &Point) -> bool {
+    pt.x >= a.x.min(b.x) && pt.x <= a.x.max(b.x) && pt.y >= a.y.min(b.y) && pt.y <= a.y.max(b.y)
+}
+
+// ray casting along +x, the points on the outline are inside.
+fn inside_or_on(hull: &[usize], points: &[Point], pt: &Point) -> bool {
+    let mut inside = false;
+    for i in 0..hull.len() {
+        let a = &points[hull[i]];
+        let b = &points[hull[(i + 1) % hull.len()]];
+        if orient2d(a, b, pt) == 0.0 && on_segment(a, b, pt) {
+            return true;
+        }
+        if (a.y > pt.y) != (b.y > pt.y) {
+            let x = a.x + (pt.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if pt.x < x {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::concave_hull;
+    use crate::convex_hull_2d::{convex_hull, convex_hull_area};
+    use crate::geometry::Point;
+    use crate::predicates::orient2d;
+
+    // a C shape: the unit grid of [0, 10] x [0, 10] without [3, 10] x [3, 7]
+    fn c_shape() -> Vec<Point> {
+        let mut data = vec![];
+        for i in 0..=10 {
+            for j in 0..=10 {
+                if !(i >= 4 && (4..=6).contains(&j)) {
+                    data.push(Point::new(i as f64, j as f64, (i + j) as f64));
+                }
+            }
+        }
+        data
+    }
+
+    fn is_ccw(hull: &[Point]) -> bool {
+        let mut area = 0.0;
+        for i in 0..hull.len() {
+            let (a, b) = (&hull[i], &hull[(i + 1) % hull.len()]);
+            area += a.x * b.y - a.y * b.x;
+        }
+        area > 0.0
+    }
+
+    #[test]
+    fn test_concave_hull_c_shape() {
+        let data = c_shape();
+        let hull = concave_hull(&data, 3).unwrap();
+        assert!(is_ccw(&hull));
+        // the area of the C is 100 - 7 * 4 = 72, the convex hull is 100. the inner corners of
+        // the notch can be cut by a diagonal.
+        let area = convex_hull_area(&hull);
+        assert!((area - 72.0).abs() <= 1.0, "{}", area);
+        let convex = convex_hull(data.clone()).unwrap();
+        assert!((convex_hull_area(&convex) - 100.0).abs() < 0.000001);
+        // z is kept
+        assert!(hull.iter().all(|pt| pt.z == pt.x + pt.y));
+    }
+
+    #[test]
+    fn test_concave_hull_large_k_is_convex() {
+        let data = c_shape();
+        let hull = concave_hull(&data, data.len()).unwrap();
+        assert!(is_ccw(&hull));
+        assert!((convex_hull_area(&hull) - 100.0).abs() < 0.000001);
+    }
+
+    #[test]
+    fn test_concave_hull_square() {
+        // collinear points on the edges are kept on the outline
+        let mut data = vec![];
+        for i in 0..5 {
+            for j in 0..5 {
+                data.push(Point::new2d(i as f64, j as f64));
+            }
+        }
+        let hull = concave_hull(&data, 4).unwrap();
+        assert!((convex_hull_area(&hull) - 16.0).abs() < 0.000001);
+        for i in 0..hull.len() {
+            let (a, b) = (&hull[i], &hull[(i + 1) % hull.len()]);
+            for pt in &data {
+                assert!(orient2d(a, b, pt) >= 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_concave_hull_errors() {
+        assert!(concave_hull(&c_shape(), 0).is_err());
+        let data = vec![
+            Point::new2d(0.0, 0.0),
+            Point::new2d(1.0, 1.0),
+            Point::new2d(0.0, 0.0),
+        ];
+        assert!(concave_hull(&data, 3).is_err());
+        let triangle = vec![
+            Point::new2d(0.0, 0.0),
+            Point::new2d(1.0, 0.0),
+            Point::new2d(0.0, 1.0),
+        ];
+        assert_eq!(concave_hull(&triangle, 3).unwrap().len(), 3);
+    }
+}
@@ -1,103 +1,255 @@
-use crate::convex_hull_2d::benchmark::run_benchmark;
-use crate::geometry::{print_pts, sort_points_by_x, Point};
-use std::sync::{Arc, Mutex};
-use std::thread;
+use crate::error::{Error, Result};
+use crate::geometry::{sort_points_by_x, Point};
+use crate::predicates::orient2d;
+use rayon::prelude::*;
 
-pub fn convex_hull(mut data: Vec<Point>) -> Vec<Point> {
+// algorithms to compute the 2D convex hull.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    Andrew,
+    JarvisMarch,
+    Chan,
+    ChanThreaded,
+}
+
+pub fn convex_hull(data: Vec<Point>) -> Result<Vec<Point>> {
+    convex_hull_with(data, Algorithm::Chan)
+}
+
+pub fn convex_hull_with(mut data: Vec<Point>, algorithm: Algorithm) -> Result<Vec<Point>> {
+    if data.len() < 3 {
+        return Ok(data);
+    }
     // sort by x
     sort_points_by_x(&mut data);
-    const SUB_HULL_COUNT: usize = 1200;
-    let result = chan_algo(&data, SUB_HULL_COUNT);
-    println!("CH pt count: {}", result.len());
-    // run_benchmark(&data);
-    print_pts(&result);
-    result
+    let result = match algorithm {
+        Algorithm::Andrew => andrew_algo(&data)?,
+        Algorithm::JarvisMarch => jarvis_march(&data),
+        Algorithm::Chan => chan_algo(&data)?,
+        Algorithm::ChanThreaded => chan_algo_threaded(&data)?,
+    };
+    Ok(result)
 }
 
-fn chan_sub_hull_range(
-    data_len: usize,
-    sub_hull_index: usize,
-    sub_hull_count: usize,
-) -> (usize, usize) {
-    let start = sub_hull_index * data_len / sub_hull_count;
-    let end = match (sub_hull_index + 1) * data_len / sub_hull_count < data_len {
-        true => (sub_hull_index + 1) * data_len / sub_hull_count,
-        false => data_len,
-    };
-    (start, end)
+// Chan's algorithm, O(n log h) for h points on the hull.
+// the hull size is guessed as m = 2^(2^t) for t = 1, 2, ..: the points are split into groups of m
+// points, the hull of each group is computed with andrew_algo, and the hull is gift wrapped around
+// the group hulls with a binary search for the tangent to each of them. the wrapping stops after m
+// steps, if the hull is not closed by then the guess is too small and it starts again with m
+// squared.
+// the result is in counter-clockwise order from the lowest point along x (then y), like
+// jarvis_march. collinear points on the hull boundary are not part of the result.
+pub fn chan_algo(data: &[Point]) -> Result<Vec<Point>> {
+    chan_algo_with(data, false)
 }
 
-#[allow(dead_code)]
-pub fn chan_algo(data: &[Point], sub_hull_count: usize) -> Vec<Point> {
-    println!("per hull: {}", data.len() / sub_hull_count);
-    let mut all_sub_hull: Vec<Point> = vec![];
+// Chan's algorithm on the current rayon pool: the group hulls are computed in parallel, and at each
+// step of the gift wrapping the tangents to the group hulls are.
+pub fn chan_algo_threaded(data: &[Point]) -> Result<Vec<Point>> {
+    chan_algo_with(data, true)
+}
 
-    let mut pt_counter = 0;
+fn chan_algo_with(data: &[Point], parallel: bool) -> Result<Vec<Point>> {
+    let pt_1st = match data.iter().min_by(|pt1, pt2| {
+        pt1.x
+            .partial_cmp(&pt2.x)
+            .unwrap()
+            .then(pt1.y.partial_cmp(&pt2.y).unwrap())
+    }) {
+        None => return Ok(vec![]),
+        Some(val) => *val,
+    };
+    for t in 1.. {
+        // 2^(2^t), saturated at the number of points
+        let m = 1usize
+            .checked_shl(1 << t)
+            .unwrap_or(usize::MAX)
+            .min(data.len());
+        let sub_hulls: Vec<Vec<Point>> = match parallel {
+            true => data
+                .par_chunks(m)
+                .map(ccw_sub_hull)
+                .collect::<Result<_>>()?,
+            false => data.chunks(m).map(ccw_sub_hull).collect::<Result<_>>()?,
+        };
+        if let Some(result) = chan_wrap(&sub_hulls, pt_1st, m, parallel) {
+            return Ok(result);
+        }
+        if m == data.len() {
+            break;
+        }
+    }
+    Err(Error::Degenerate(String::from(
+        "Chan's algorithm did not close the hull",
+    )))
+}
 
-    for i in 0..sub_hull_count {
-        let (start, end) = chan_sub_hull_range(data.len(), i, sub_hull_count);
-        pt_counter += data[start..end].len();
-        let sub_hull = andrew_algo(&data[start..end]);
-        all_sub_hull.extend(sub_hull);
+// hull of a group of points in counter-clockwise order, without duplicated points
+fn ccw_sub_hull(data: &[Point]) -> Result<Vec<Point>> {
+    if data.len() < 2 {
+        return Ok(data.to_vec());
     }
-    if data.len() != pt_counter {
-        eprintln!("{} != {}", data.len(), pt_counter);
-        panic!("point count does not match");
+    let mut sorted = data.to_vec();
+    sort_points_by_x(&mut sorted);
+    let mut hull = andrew_algo(&sorted)?;
+    // andrew_algo is clockwise
+    hull.reverse();
+    hull.dedup();
+    if hull.len() > 1 && hull.first() == hull.last() {
+        hull.pop();
     }
-    let result = jarvis_march(all_sub_hull.as_slice());
-    result
+    Ok(hull)
 }
 
-#[allow(dead_code)]
-pub fn chan_algo_threaded(data: Vec<Point>, sub_hull_count: usize) -> Vec<Point> {
-    println!("per hull: {}", data.len() / sub_hull_count);
-    let all_sub_hull: Arc<Mutex<Vec<Point>>> = Arc::new(Mutex::new(vec![]));
-    let input_data = Arc::new(data);
-
-    let mut thread_handles = vec![];
-    for index in 0..sub_hull_count {
-        let input_data = Arc::clone(&input_data);
-        let all_sub_hull = Arc::clone(&all_sub_hull);
-        let handle = thread::spawn(move || {
-            let (start, end) = chan_sub_hull_range(input_data.len(), index, sub_hull_count);
-            let sub_hull = andrew_algo(&input_data[start..end]);
-            {
-                let mut val = all_sub_hull.lock().unwrap();
-                val.extend(sub_hull);
+// gift wrapping around the sub hulls from pt_1st, None if the hull has more than max_steps points.
+// with parallel, the candidates of the sub hulls at each step are found on the current rayon pool.
+fn chan_wrap(
+    sub_hulls: &[Vec<Point>],
+    pt_1st: Point,
+    max_steps: usize,
+    parallel: bool,
+) -> Option<Vec<Point>> {
+    // the hull point is (sub hull, index in the sub hull)
+    let mut current = sub_hulls
+        .iter()
+        .enumerate()
+        .find_map(|(h, hull)| hull.iter().position(|pt| *pt == pt_1st).map(|i| (h, i)))?;
+    let mut result = vec![pt_1st];
+    for _ in 0..max_steps {
+        let pt1 = sub_hulls[current.0][current.1];
+        let candidate = |h: usize| chan_candidate(sub_hulls, current, h);
+        let select = |pt2: (usize, usize), pt3: (usize, usize)| {
+            // same choice as select_pt
+            let (pt2_val, pt3_val) = (&sub_hulls[pt2.0][pt2.1], &sub_hulls[pt3.0][pt3.1]);
+            let ori = orientation(&pt1, pt2_val, pt3_val);
+            match ori < 0 || (ori == 0 && distance_2d(&pt1, pt3_val) > distance_2d(&pt1, pt2_val)) {
+                true => pt3,
+                false => pt2,
             }
-        });
-        thread_handles.push(handle);
+        };
+        let selected = match parallel {
+            true => (0..sub_hulls.len())
+                .into_par_iter()
+                .filter_map(candidate)
+                .reduce_with(select),
+            false => (0..sub_hulls.len()).filter_map(candidate).reduce(select),
+        };
+        current = match selected {
+            // every point is pt_1st
+            None => return Some(result),
+            Some(val) => val,
+        };
+        let selected = sub_hulls[current.0][current.1];
+        if selected == pt_1st {
+            return Some(result);
+        }
+        result.push(selected);
+    }
+    None
+}
+
+// the next hull point after current among the points of sub hull h
+fn chan_candidate(
+    sub_hulls: &[Vec<Point>],
+    current: (usize, usize),
+    h: usize,
+) -> Option<(usize, usize)> {
+    let hull = &sub_hulls[h];
+    let index = match h == current.0 {
+        // the next point of its own sub hull
+        true if hull.len() > 1 => (current.1 + 1) % hull.len(),
+        true => return None,
+        false => tangent(hull, &sub_hulls[current.0][current.1])?,
+    };
+    Some((h, index))
+}
+
+// index of the point of the counter-clockwise convex polygon hull that has all the other points on
+// the left of pt1->pt, the farthest one if some are on the line. pt1 is outside of the polygon.
+// the angle of the polygon points seen from pt1 goes down from the left tangent to the right
+// tangent, then up back to the left tangent: the right tangent is found by binary search.
+fn tangent(hull: &[Point], pt1: &Point) -> Option<usize> {
+    let len = hull.len();
+    if len <= 3 {
+        return tangent_linear(hull, pt1);
     }
-    for handle in thread_handles {
-        handle.join().unwrap();
+    let ori = |i: usize, j: usize| orientation(pt1, &hull[i % len], &hull[j % len]);
+    // the edge from i goes down, clockwise seen from pt1
+    let down = |i: usize| ori(i, i + 1) < 0;
+    let found = if !down(0) && down(len - 1) {
+        0
+    } else {
+        // i is before the right tangent. if the first edge goes down, the points before the
+        // tangent go down and are below the first point, the points after the left tangent are
+        // above it. otherwise the points before the left tangent go up and are above the first
+        // point, the ones between the tangents go down, and the ones after the right tangent are
+        // below the first point.
+        let first_down = down(0);
+        let before = |i: usize| match first_down {
+            true => down(i) && ori(0, i) < 0,
+            false => down(i) || ori(0, i) > 0,
+        };
+        let (mut low, mut high) = (1, len - 1);
+        while low < high {
+            let mid = (low + high) / 2;
+            if before(mid) {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        low
+    };
+    // the farthest of the points on the tangent line
+    let mut found = found;
+    for next in [found + 1, found + len - 1] {
+        let next = next % len;
+        if ori(found, next) == 0 && distance_2d(pt1, &hull[next]) > distance_2d(pt1, &hull[found]) {
+            found = next;
+        }
     }
+    // pt1 on the polygon (a duplicated point) breaks the binary search, check the neighbors
+    let is_tangent =
+        hull[found] != *pt1 && ori(found, found + 1) >= 0 && ori(found, found + len - 1) >= 0;
+    match is_tangent {
+        true => Some(found),
+        false => tangent_linear(hull, pt1),
+    }
+}
 
-    let result = jarvis_march(all_sub_hull.lock().unwrap().as_slice());
-    result
+fn tangent_linear(hull: &[Point], pt1: &Point) -> Option<usize> {
+    let selected = select_pt(hull, pt1)?;
+    hull.iter().position(|pt| *pt == selected)
 }
 
-#[allow(dead_code)]
-pub fn andrew_algo_sort(data: &mut Vec<Point>) -> Vec<Point> {
+pub fn andrew_algo_sort(data: &mut [Point]) -> Result<Vec<Point>> {
     sort_points_by_x(data);
     andrew_algo(data)
 }
 
-// Note: this assumes data is pre-sort by x-coordinate.
-pub fn andrew_algo(data: &[Point]) -> Vec<Point> {
-    let mut result: Vec<Point> = vec![data.get(0).unwrap().clone(), data.get(1).unwrap().clone()];
+// Note: this assumes data is pre-sort by x-coordinate (then y-coordinate).
+// collinear points on the hull boundary are not part of the result.
+pub fn andrew_algo(data: &[Point]) -> Result<Vec<Point>> {
+    if data.len() < 2 {
+        return Err(Error::NotEnoughPoints {
+            required: 2,
+            found: data.len(),
+        });
+    }
+    let mut result: Vec<Point> = vec![data[0], data[1]];
     // upper hull
     for pt in (data[2..]).iter() {
         while result.len() >= 2 {
             let last = result.last().unwrap();
             let second_last = result.get(result.len() - 2).unwrap();
-            if orientation(second_last, last, pt) > 0 {
+            if orientation(second_last, last, pt) >= 0 {
                 result.pop();
             } else {
                 break;
             }
         }
         if pt != result.last().unwrap() {
-            result.push(pt.clone());
+            result.push(*pt);
         }
     }
     let upper_hull_len = result.len();
@@ -105,28 +257,34 @@ pub fn andrew_algo(data: &[Point]) -> Vec<Point> {
 
     // lower hull
     for pt in data.iter().rev() {
-        while result.len() >= upper_hull_len {
+        // never pop the last point of the upper hull
+        while result.len() > upper_hull_len {
             let last = result.last().unwrap();
             let second_last = result.get(result.len() - 2).unwrap();
-            if orientation(second_last, last, pt) > 0 {
+            if orientation(second_last, last, pt) >= 0 {
                 result.pop();
             } else {
                 break;
             }
         }
-        if pt != result.get(0).unwrap() && pt != result.last().unwrap() {
-            result.push(pt.clone());
+        if pt != result.first().unwrap() && pt != result.last().unwrap() {
+            result.push(*pt);
         }
     }
-    result
+    Ok(result)
 }
 
+// the result is in counter-clockwise order, collinear points on the hull boundary are not part of
+// the result.
 pub fn jarvis_march(data: &[Point]) -> Vec<Point> {
-    let pt_1st = match data.get(0) {
-        None => {
-            eprintln!("jarvis_march, empty input");
-            return vec![];
-        }
+    // the lowest point along x (then y) is always on the hull
+    let pt_1st = match data.iter().min_by(|pt1, pt2| {
+        pt1.x
+            .partial_cmp(&pt2.x)
+            .unwrap()
+            .then(pt1.y.partial_cmp(&pt2.y).unwrap())
+    }) {
+        None => return vec![],
         Some(val) => val,
     };
 
@@ -148,49 +306,42 @@ pub fn jarvis_march(data: &[Point]) -> Vec<Point> {
     result
 }
 
+// selected pt2 must have all other points on the left of pt1->pt2,
+// if some points are on the line pt1->pt2, pt2 is the farthest one.
 fn select_pt(data: &[Point], last_hull_pt: &Point) -> Option<Point> {
     let pt1 = last_hull_pt;
-    for pt2 in data {
-        if pt2 == last_hull_pt {
+    let mut selected: Option<&Point> = None;
+    for pt3 in data {
+        if pt3 == last_hull_pt {
             continue;
         }
-
-        // eprintln!("pt1: {}", pt1);
-        // eprintln!("pt2: {}", pt2);
-
-        let mut ok = true;
-        // selected pt2 must have all other points on the right of pt1->pt2
-        for pt3 in data {
-            if pt3 == last_hull_pt {
-                continue;
-            }
-            if pt3 == pt2 {
+        let pt2 = match selected {
+            None => {
+                selected = Some(pt3);
                 continue;
             }
-            let ori = orientation(pt1, pt2, pt3);
-            // eprintln!("pt3 {}, ori {}", pt3, ori);
-            if ori <= 0 {
-                ok = false;
-                break;
-            }
-        }
-        if ok {
-            return Some((*pt2).clone());
+            Some(val) => val,
+        };
+        let ori = orientation(pt1, pt2, pt3);
+        if ori < 0 || (ori == 0 && distance_2d(pt1, pt3) > distance_2d(pt1, pt2)) {
+            selected = Some(pt3);
         }
     }
-    None
+    selected.copied()
+}
+
+fn distance_2d(pt1: &Point, pt2: &Point) -> f64 {
+    ((pt2.x - pt1.x) * (pt2.x - pt1.x) + (pt2.y - pt1.y) * (pt2.y - pt1.y)).sqrt()
 }
 
 // pt1->pt2 X pt1->pt3
-fn orientation(pt1: &Point, pt2: &Point, pt3: &Point) -> i32 {
-    // ax * by - ay * bx
-    let z = (pt2.x - pt1.x) * (pt3.y - pt1.y) - (pt2.y - pt1.y) * (pt3.x - pt1.x);
-    let result = if z == 0.0 {
+pub(crate) fn orientation(pt1: &Point, pt2: &Point, pt3: &Point) -> i32 {
+    let z = orient2d(pt1, pt2, pt3);
+    if z == 0.0 {
         0
     } else if z > 0.0 {
         1
     } else {
         -1
-    };
-    result
+    }
 }
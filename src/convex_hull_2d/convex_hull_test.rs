@@ -1,16 +1,23 @@
 #[cfg(test)]
 mod tests {
-    use super::super::convex_hull::{andrew_algo, jarvis_march};
-    use crate::convex_hull_2d::convex_hull::andrew_algo_sort;
-    use crate::geometry::{sort_points_by_x, Point};
+    use super::super::convex_hull::jarvis_march;
+    use crate::convex_hull_2d::convex_hull::{
+        andrew_algo_sort, chan_algo, chan_algo_threaded, convex_hull,
+    };
+    use crate::error::Error;
+    use crate::geometry::Point;
+    use crate::predicates::orient2d;
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
 
-    struct convex_hull_test_case {
+    struct ConvexHullTestCase {
         data: Vec<Point>,
         expected_hull: Vec<Point>,
     }
-    fn all_convex_hull_test_cases() -> Vec<convex_hull_test_case> {
+    fn all_convex_hull_test_cases() -> Vec<ConvexHullTestCase> {
         vec![
-            convex_hull_test_case {
+            ConvexHullTestCase {
                 data: vec![
                     Point::new2d_int(262, -103),
                     Point::new2d_int(93, 120),
@@ -28,7 +35,7 @@ mod tests {
                     Point::new2d_int(-78, -114),
                 ],
             },
-            convex_hull_test_case {
+            ConvexHullTestCase {
                 data: vec![
                     Point::new2d_int(-279, -115),
                     Point::new2d_int(-247, -41),
@@ -45,7 +52,7 @@ mod tests {
                     Point::new2d_int(235, -130),
                 ],
             },
-            convex_hull_test_case {
+            ConvexHullTestCase {
                 data: vec![
                     Point::new2d_int(-270, 45),
                     Point::new2d_int(-43, -38),
@@ -61,7 +68,7 @@ mod tests {
                     Point::new2d_int(126, -140),
                 ],
             },
-            convex_hull_test_case {
+            ConvexHullTestCase {
                 data: vec![
                     Point::new2d_int(-202, 85),
                     Point::new2d_int(-99, 109),
@@ -85,7 +92,7 @@ mod tests {
                     Point::new2d_int(-97, -116),
                 ],
             },
-            convex_hull_test_case {
+            ConvexHullTestCase {
                 data: vec![
                     Point::new2d_int(-21, -124),
                     Point::new2d_int(272, 68),
@@ -127,9 +134,9 @@ mod tests {
     fn test_andrew_scan() {
         let all_test_cases = all_convex_hull_test_cases();
 
-        for (i, test_case) in all_test_cases.iter().enumerate() {
+        for test_case in all_test_cases.iter() {
             let mut test_data = test_case.data.clone();
-            let hull = andrew_algo_sort(&mut test_data);
+            let hull = andrew_algo_sort(&mut test_data).unwrap();
             assert_eq!(hull.len(), test_case.expected_hull.len());
             for pt1 in &hull {
                 assert!(test_case.expected_hull.contains(pt1));
@@ -137,9 +144,140 @@ mod tests {
         }
     }
 
-    fn print_pt_list(data: &[Point]) {
-        for pt in data {
-            println!("{}", pt);
+    // inputs with collinear points on the hull boundary, and duplicated points
+    fn degenerated_convex_hull_test_cases() -> Vec<ConvexHullTestCase> {
+        let mut grid = vec![];
+        for x in 0..5 {
+            for y in 0..5 {
+                grid.push(Point::new2d_int(x, y));
+            }
+        }
+        vec![
+            ConvexHullTestCase {
+                data: grid,
+                expected_hull: vec![
+                    Point::new2d_int(0, 0),
+                    Point::new2d_int(4, 0),
+                    Point::new2d_int(4, 4),
+                    Point::new2d_int(0, 4),
+                ],
+            },
+            ConvexHullTestCase {
+                data: vec![
+                    Point::new2d_int(0, 0),
+                    Point::new2d_int(3, 3),
+                    Point::new2d_int(1, 1),
+                    Point::new2d_int(6, 0),
+                    Point::new2d_int(2, 2),
+                    Point::new2d_int(3, 0),
+                    Point::new2d_int(6, 0),
+                    Point::new2d_int(4, 1),
+                ],
+                expected_hull: vec![
+                    Point::new2d_int(0, 0),
+                    Point::new2d_int(6, 0),
+                    Point::new2d_int(3, 3),
+                ],
+            },
+        ]
+    }
+
+    #[test]
+    fn test_degenerated_convex_hull() {
+        for test_case in degenerated_convex_hull_test_cases() {
+            let mut test_data = test_case.data.clone();
+            let all_hulls = vec![
+                jarvis_march(&test_case.data),
+                andrew_algo_sort(&mut test_data).unwrap(),
+                convex_hull(test_case.data.clone()).unwrap(),
+            ];
+            for hull in all_hulls {
+                assert_eq!(hull.len(), test_case.expected_hull.len());
+                for pt1 in &hull {
+                    assert!(test_case.expected_hull.contains(pt1));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_not_enough_points() {
+        assert!(matches!(
+            andrew_algo_sort(&mut [Point::new2d_int(1, 1)]),
+            Err(Error::NotEnoughPoints {
+                required: 2,
+                found: 1
+            })
+        ));
+        let data = vec![Point::new2d_int(0, 0), Point::new2d_int(1, 1)];
+        assert!(chan_algo(&[]).unwrap().is_empty());
+        assert_eq!(chan_algo(&data).unwrap(), data);
+        assert_eq!(chan_algo(&[data[1], data[1]]).unwrap(), vec![data[1]]);
+        assert_eq!(chan_algo_threaded(&data).unwrap(), data);
+        // too few points for a polygon, the points are the hull
+        assert_eq!(convex_hull(data).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_chan_algo() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut circle = vec![];
+        for k in 0..1000 {
+            let angle = k as f64 * 2.0 * std::f64::consts::PI / 1000.0;
+            circle.push(Point::new2d(angle.cos(), angle.sin()));
+        }
+        circle.shuffle(&mut rng);
+        let mut grid = vec![];
+        for x in 0..30 {
+            for y in 0..30 {
+                // every point twice
+                grid.push(Point::new2d_int(x % 15, y));
+            }
+        }
+        grid.shuffle(&mut rng);
+        let random: Vec<Point> = (0..5000)
+            .map(|_| Point::new2d(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0)))
+            .collect();
+        let mut test_data = vec![circle, grid, random];
+        for test_case in all_convex_hull_test_cases() {
+            test_data.push(test_case.data);
+        }
+        for test_case in degenerated_convex_hull_test_cases() {
+            test_data.push(test_case.data);
+        }
+        // the points on a circle need 3 rounds of guesses, 16 and 256 are too small
+        for data in test_data {
+            // same points in the same order
+            let jarvis = jarvis_march(&data);
+            assert_eq!(chan_algo(&data).unwrap(), jarvis);
+            assert_eq!(chan_algo_threaded(&data).unwrap(), jarvis);
+        }
+    }
+
+    #[test]
+    fn test_near_collinear_convex_hull() {
+        // points within a few ulp of the line y = x,
+        // the sign of a plain f64 cross product is wrong on some of them
+        let ulp = f64::EPSILON / 2.0;
+        let mut data = vec![Point::new2d(12.0, 12.0), Point::new2d(24.0, 24.0)];
+        for i in 0..8 {
+            for j in 0..8 {
+                data.push(Point::new2d(0.5 + i as f64 * ulp, 0.5 + j as f64 * ulp));
+            }
+        }
+        let jarvis = jarvis_march(&data);
+        let andrew = andrew_algo_sort(&mut data.clone()).unwrap();
+        assert_eq!(jarvis.len(), andrew.len());
+        for hull in [&jarvis, &andrew] {
+            // every point is on the inner side (or on) every edge of the hull
+            let ccw = orient2d(&hull[0], &hull[1], &hull[2]) > 0.0;
+            for i in 0..hull.len() {
+                let (pt1, pt2) = (&hull[i], &hull[(i + 1) % hull.len()]);
+                for pt in &data {
+                    let ori = orient2d(pt1, pt2, pt);
+                    assert!(if ccw { ori >= 0.0 } else { ori <= 0.0 });
+                }
+            }
         }
     }
 }
@@ -1,8 +1,14 @@
 mod area;
 mod benchmark;
+mod concave_hull;
 mod convex_hull;
 mod convex_hull_test;
 
-pub use area::convex_hull_area;
-pub use area::convex_hull_area_reorder;
-pub use convex_hull::convex_hull;
+pub use area::{convex_hull_area, convex_hull_area_reorder};
+pub use benchmark::run_benchmark_with;
+pub use concave_hull::concave_hull;
+pub(crate) use convex_hull::orientation;
+pub use convex_hull::{
+    andrew_algo, andrew_algo_sort, chan_algo, chan_algo_threaded, convex_hull, convex_hull_with,
+    jarvis_march, Algorithm,
+};
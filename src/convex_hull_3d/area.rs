@@ -0,0 +1,66 @@
+use crate::geometry::{Triangle, Vec3D};
+
+// compute the surface area of the 3D hull, sum of the area of all triangles.
+// Note: this does not depend on the winding of the triangles.
+pub fn convex_hull_surface_area(hull: &[Triangle]) -> f64 {
+    let mut area = 0.0f64;
+    for tri in hull {
+        area += triangle_area(tri);
+    }
+    area
+}
+
+// half of the length of p1->p2 X p1->p3
+fn triangle_area(tri: &Triangle) -> f64 {
+    let a = Vec3D::new(
+        tri.p2.x - tri.p1.x,
+        tri.p2.y - tri.p1.y,
+        tri.p2.z - tri.p1.z,
+    );
+    let b = Vec3D::new(
+        tri.p3.x - tri.p1.x,
+        tri.p3.y - tri.p1.y,
+        tri.p3.z - tri.p1.z,
+    );
+    let x = a.y * b.z - a.z * b.y;
+    let y = a.z * b.x - a.x * b.z;
+    let z = a.x * b.y - a.y * b.x;
+    (x * x + y * y + z * z).sqrt() / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::convex_hull_surface_area;
+    use crate::geometry::{Point, Triangle};
+
+    const TOLERANCE: f64 = 0.000001;
+
+    #[test]
+    fn test_surface_area_tetrahedron() {
+        let o = Point::new(0.0, 0.0, 0.0);
+        let a = Point::new(1.0, 0.0, 0.0);
+        let b = Point::new(0.0, 1.0, 0.0);
+        let c = Point::new(0.0, 0.0, 1.0);
+        let hull = vec![
+            Triangle::new(o, a, b),
+            Triangle::new(o, c, a),
+            Triangle::new(o, b, c),
+            Triangle::new(a, c, b),
+        ];
+        // 3 right triangles and 1 equilateral triangle with side sqrt(2)
+        let expected = 3.0 * 0.5 + 3.0f64.sqrt() / 4.0 * 2.0;
+        let area = convex_hull_surface_area(&hull);
+        assert!((area - expected).abs() < TOLERANCE, "area: {}", area);
+    }
+
+    #[test]
+    fn test_surface_area_degenerated() {
+        let hull = vec![Triangle::new(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(1.0, 1.0, 1.0),
+            Point::new(2.0, 2.0, 2.0),
+        )];
+        assert_eq!(convex_hull_surface_area(&hull), 0.0);
+        assert_eq!(convex_hull_surface_area(&[]), 0.0);
+    }
+}
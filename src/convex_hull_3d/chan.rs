@@ -1,30 +1,31 @@
 use super::jarvis_march;
-use crate::geometry::{sort_points_by_x, triangles_to_pts, Edge, Point, Triangle, Vec3D};
+use super::volume::convex_hull_volume;
+use crate::error::{Error, Result};
+use crate::geometry::{sort_points_by_x, triangles_to_pts, Point, Triangle};
+use rayon::prelude::*;
 
-pub fn run(mut data: Vec<Point>) -> Vec<Triangle> {
+// the sub hulls of slices along x are computed in parallel on the current rayon pool, each in its
+// own vector, then the hull of their vertices is gift wrapped with the candidates tested in parallel.
+pub fn run(mut data: Vec<Point>) -> Result<Vec<Triangle>> {
     sort_points_by_x(&mut data);
-    let mut sub_hull_count: usize = 1000;
-    if data.len() < 1000 {
-        sub_hull_count = 1;
-    } else if data.len() < 10000 {
-        sub_hull_count = 100;
-    }
-    println!("per hull: {}", data.len() / sub_hull_count);
-    let mut all_sub_hull_result: Vec<Triangle> = vec![];
-    // for (index, chunk) in data.chunks(sub_hull_count).enumerate() {
-    //     let mut data_slice: Vec<Point> = vec![];
-    //     data_slice.extend_from_slice(&chunk);
-    //     let result = jarvis_march::run(data_slice);
-    //     all_sub_hull_result.extend(result);
-    // }
-    for index in 0..sub_hull_count {
-        let (start, end) = chan_sub_hull_range(data.len(), index, sub_hull_count);
-        let mut data_slice: Vec<Point> = vec![];
-        data_slice.extend_from_slice(&data[start..end]);
-        let result = jarvis_march::run(data_slice);
-        all_sub_hull_result.extend(result);
-    }
-    jarvis_march::run(triangles_to_pts(all_sub_hull_result))
+    // sqrt(n) slices of sqrt(n) points
+    let sub_hull_count = ((data.len() as f64).sqrt() as usize).max(1);
+    let sub_hull_pts: Vec<Point> = (0..sub_hull_count)
+        .into_par_iter()
+        .map(|index| {
+            let (start, end) = chan_sub_hull_range(data.len(), index, sub_hull_count);
+            let slice = &data[start..end];
+            match jarvis_march::run(slice.to_vec()) {
+                Ok(hull) if convex_hull_volume(&hull) > 0.0 => Ok(triangles_to_pts(hull)),
+                // less than 3 points, collinear or coplanar points: no sub hull with a volume,
+                // the points are merged as is
+                Ok(_) | Err(Error::Degenerate(_)) => Ok(slice.to_vec()),
+                Err(err) => Err(err),
+            }
+        })
+        .collect::<Result<Vec<_>>>()?
+        .concat();
+    jarvis_march::run_parallel(sub_hull_pts)
 }
 
 fn chan_sub_hull_range(
@@ -43,7 +44,8 @@ fn chan_sub_hull_range(
 #[cfg(test)]
 mod tests {
     use super::run as chan;
-    use crate::geometry::{print_pts, sort_points_by_x, Point};
+    use crate::convex_hull_3d::{convex_hull, convex_hull_volume};
+    use crate::geometry::Point;
     use rand::prelude::ThreadRng;
     use rand::{thread_rng, Rng};
 
@@ -70,10 +72,7 @@ mod tests {
             Point::new(0.23000306733390696, 0.07355483001280383, 0.5170732459676636),
         ];
 
-        let result = chan(test_data);
-        for tri in result {
-            println!("{}, {}, {}", tri.p1, tri.p2, tri.p3);
-        }
+        assert_same_as_quickhull(test_data);
     }
 
     #[test]
@@ -95,25 +94,58 @@ mod tests {
             Point::new(0.1904018057684721, 0.3093473600982406, 0.7978016509510075),
         ];
 
-        let result = chan(test_data);
-        for tri in result {
-            println!("{}, {}, {}", tri.p1, tri.p2, tri.p3);
-        }
+        assert_same_as_quickhull(test_data);
     }
 
     #[test]
     fn test_chan_3d_rand_10() {
         for _ in 0..10 {
-            let mut rng: ThreadRng = thread_rng();
-            let mut test_data = generate_test_data(10);
-            print_pts(&test_data);
+            assert_same_as_quickhull(generate_test_data(10));
+        }
+    }
 
-            let result = chan(test_data);
-            for tri in result {
-                println!("{}, {}, {}", tri.p1, tri.p2, tri.p3);
+    #[test]
+    fn test_chan_3d_sub_hulls() {
+        // 31 sub hulls, same hull as quickhull
+        assert_same_as_quickhull(generate_test_data(1000));
+    }
+
+    #[test]
+    fn test_chan_3d_collinear_slice() {
+        // the first slice is points on a line at the lowest x, it has no sub hull
+        let mut test_data: Vec<Point> = (0..10)
+            .map(|i| Point::new(-1.0, i as f64 * 0.1, i as f64 * 0.05))
+            .collect();
+        test_data.extend(generate_test_data(90));
+        assert_same_as_quickhull(test_data);
+    }
+
+    #[test]
+    fn test_chan_3d_grid() {
+        // a 3 x 3 x 3 grid, every point twice: the slices are planes of the grid
+        let mut test_data = vec![];
+        for _ in 0..2 {
+            for i in 0..3 {
+                for j in 0..3 {
+                    for k in 0..3 {
+                        test_data.push(Point::new(i as f64, j as f64, k as f64));
+                    }
+                }
             }
-            println!("-----\n\n")
         }
+        let expected = convex_hull_volume(&convex_hull(test_data.clone()).unwrap());
+        assert!((expected - 8.0).abs() < 0.000001);
+        let volume = convex_hull_volume(&chan(test_data).unwrap());
+        assert!((volume - expected).abs() < 0.000001, "{}", volume);
+    }
+
+    // random points are in general position, both hulls have the same triangles
+    fn assert_same_as_quickhull(data: Vec<Point>) {
+        let expected = convex_hull(data.clone()).unwrap();
+        let result = chan(data).unwrap();
+        assert_eq!(result.len(), expected.len());
+        let volume = convex_hull_volume(&expected);
+        assert!((convex_hull_volume(&result) - volume).abs() < 0.000001);
     }
 
     fn generate_test_data(count: usize) -> Vec<Point> {
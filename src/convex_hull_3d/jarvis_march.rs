@@ -1,14 +1,29 @@
-use crate::geometry::{sort_points_by_x, Edge, Point, Triangle, Vec3D};
+use crate::error::{Error, Result};
+use crate::geometry::{Point, Triangle};
+use crate::predicates::{collinear3d, orient2d, orient3d};
+use rayon::prelude::*;
 use std::cmp::Ordering;
+use std::collections::HashSet;
 
 // https://dccg.upc.edu/people/vera/wp-content/uploads/2014/11/GA2014-ConvexHulls3D-Roger-Hernando.pdf
 
-pub fn run(mut data: Vec<Point>) -> Vec<Triangle> {
+pub fn run(data: Vec<Point>) -> Result<Vec<Triangle>> {
+    run_with(data, false)
+}
+
+// same hull, the candidates for the 3rd vertex of each triangle are tested on the current rayon pool
+pub fn run_parallel(data: Vec<Point>) -> Result<Vec<Triangle>> {
+    run_with(data, true)
+}
+
+fn run_with(mut data: Vec<Point>, parallel: bool) -> Result<Vec<Triangle>> {
     sort_points_by_xyz(&mut data);
-    run_sorted(data)
+    // duplicated points would be picked as vertex of more than one triangle on the same plane
+    data.dedup_by(|pt1, pt2| pt1.x == pt2.x && pt1.y == pt2.y && pt1.z == pt2.z);
+    run_sorted(data, parallel)
 }
 
-fn sort_points_by_xyz(data: &mut Vec<Point>) {
+fn sort_points_by_xyz(data: &mut [Point]) {
     data.sort_by(|pt1, pt2| {
         if pt1.x < pt2.x {
             return Ordering::Less;
@@ -29,168 +44,252 @@ fn sort_points_by_xyz(data: &mut Vec<Point>) {
     });
 }
 
-pub fn run_sorted(mut data: Vec<Point>) -> Vec<Triangle> {
-    let mut result: Vec<Triangle> = vec![];
-    let mut queue: Vec<Edge> = vec![first_edge(&mut data)];
-    while !queue.is_empty() && !data.is_empty() {
-        let edge = queue.pop().unwrap();
-        let p3 = match find_triangle_third_vertex(&mut data, edge) {
+// Note: points are referred by index, so that a vertex can be shared by any number of triangles.
+// every triangle is wound so that its normal (p1->p2 X p1->p3) points to the inside of the hull,
+// the neighbor across edge (a, b) is therefore the triangle that contains the edge (b, a).
+pub fn run_sorted(data: Vec<Point>, parallel: bool) -> Result<Vec<Triangle>> {
+    if data.len() < 3 {
+        return Ok(vec![]);
+    }
+    let first = match first_triangle(&data, parallel) {
+        Some(val) => val,
+        None => {
+            return Err(Error::Degenerate(String::from(
+                "no 1st triangle, all points are collinear",
+            )))
+        }
+    };
+
+    let (p1, p2, p3) = (&data[first[0]], &data[first[1]], &data[first[2]]);
+    if data.iter().all(|pt| orient3d(p1, p2, p3, pt) == 0.0) {
+        // the wrap would stop after one triangle on each side
+        return Ok(polygon_fan(&data, first));
+    }
+
+    let mut result: Vec<[usize; 3]> = vec![];
+    let mut edges: HashSet<(usize, usize)> = HashSet::new();
+    let mut queue: Vec<(usize, usize)> = vec![];
+    add_triangle(first, &mut result, &mut edges, &mut queue);
+
+    while let Some((a, b)) = queue.pop() {
+        if edges.contains(&(b, a)) {
+            // the other side of the edge is already wrapped
+            continue;
+        }
+        let p3 = match find_triangle_third_vertex_index(&data, b, a, parallel) {
             Some(val) => val,
             None => {
-                if result.is_empty() {
-                    panic!("1st edge is bad")
-                }
-                eprintln!(
-                    "result.len(): {}\tremained_data.len(): {}",
+                return Err(Error::Degenerate(format!(
+                    "no 3rd point selected for edge ({}, {}), {} triangles on {} points",
+                    data[b],
+                    data[a],
                     result.len(),
                     data.len()
-                );
-                panic!("no 3rd point selected")
+                )))
             }
         };
-        queue.push(Edge::new(edge.p1.clone(), p3.clone()));
-        queue.push(Edge::new(edge.p2.clone(), p3.clone()));
-        result.push(Triangle::new(edge.p1.clone(), edge.p2.clone(), p3));
-        // progress_indicator(&result, &data);
+        add_triangle([b, a, p3], &mut result, &mut edges, &mut queue);
     }
-    result
+    Ok(retriangulate_coplanar_faces(&data, result)
+        .iter()
+        .map(|tri| Triangle::new(data[tri[0]], data[tri[1]], data[tri[2]]))
+        .collect())
 }
 
-fn progress_indicator(result: &Vec<Triangle>, remained_data: &Vec<Point>) {
-    if result.len() % 2 == 0 {
-        println!(
-            "result.len(): {}\tremained_data.len(): {}",
-            result.len(),
-            remained_data.len()
-        );
+fn add_triangle(
+    tri: [usize; 3],
+    result: &mut Vec<[usize; 3]>,
+    edges: &mut HashSet<(usize, usize)>,
+    queue: &mut Vec<(usize, usize)>,
+) {
+    for (a, b) in [(tri[0], tri[1]), (tri[1], tri[2]), (tri[2], tri[0])] {
+        if edges.insert((a, b)) {
+            queue.push((a, b));
+        }
     }
+    result.push(tri);
 }
 
-// fn first_edge(data: &mut Vec<Point>) -> Edge {
-//     let p1 = data.pop().unwrap();
-//     let p2 = data.pop().unwrap();
-//     Edge::new(p1, p2)
-// }
-
-fn first_edge(data: &mut Vec<Point>) -> Edge {
-    let mut index1: Option<usize> = None;
-    let mut index2: Option<usize> = None;
-    let mut selected_p2: Option<Point> = None;
-    for (i, p1) in data.iter().enumerate() {
-        for (j, p2) in data[i + 1..].iter().enumerate() {
-            let j = j + i + 1;
-            match find_triangle_third_vertex_index(data, Edge::new(p1.clone(), p2.clone())) {
-                None => continue,
-                Some(_) => {
-                    index1 = Some(i);
-                    if j > i {
-                        index2 = Some(j - 1);
-                    } else {
-                        index2 = Some(j);
-                    }
-                    selected_p2 = Some(p2.clone());
-                    break;
-                }
-            };
+// data[0] is the smallest point in xyz order, which is always a vertex of the hull.
+fn first_triangle(data: &[Point], parallel: bool) -> Option<[usize; 3]> {
+    for j in 1..data.len() {
+        if let Some(k) = find_triangle_third_vertex_index(data, 0, j, parallel) {
+            return Some([0, j, k]);
+        }
+        if let Some(k) = find_triangle_third_vertex_index(data, j, 0, parallel) {
+            return Some([j, 0, k]);
         }
     }
-    let p1 = data.remove(index1.unwrap());
-    let p2 = data.remove(index2.unwrap());
-    if p2 != selected_p2.unwrap() {
-        panic!("bug, p2 is not the same as selected one")
-    }
-    Edge::new(p1, p2)
+    None
 }
 
-// pick a point to form triangle with the edge.
-// the triangle will have all other points on one side of the plane it is on.
-fn find_triangle_third_vertex(data: &mut Vec<Point>, edge: Edge) -> Option<Point> {
-    match find_triangle_third_vertex_index(data, edge) {
-        Some(i) => {
-            let p3 = data.remove(i);
-            Some(p3)
-        }
-        None => None, // panic!("no third point found to form a good triangle")
+// pick a point to form triangle (p1, p2, p3) with the edge.
+// the triangle will have all other points on the inner side of the plane it is on.
+// the first such point is picked, also in parallel, so that the hull does not depend on the pool.
+fn find_triangle_third_vertex_index(
+    data: &[Point],
+    p1: usize,
+    p2: usize,
+    parallel: bool,
+) -> Option<usize> {
+    let is_third = |i: &usize| *i != p1 && *i != p2 && all_pts_on_inside(data, [p1, p2, *i]);
+    match parallel {
+        true => (0..data.len()).into_par_iter().find_first(is_third),
+        false => (0..data.len()).find(is_third),
     }
 }
 
-fn find_triangle_third_vertex_index(data: &Vec<Point>, edge: Edge) -> Option<usize> {
-    let mut index: Option<usize> = None;
+fn all_pts_on_inside(data: &[Point], tri: [usize; 3]) -> bool {
+    let (p1, p2, p3) = (&data[tri[0]], &data[tri[1]], &data[tri[2]]);
+    if collinear3d(p1, p2, p3) {
+        // degenerated triangle
+        return false;
+    }
     for (i, pt) in data.iter().enumerate() {
-        if pt == &edge.p1 || pt == &edge.p2 {
+        if tri.contains(&i) {
+            // skip the points of the triangle
             continue;
         }
-        let tri = Triangle::new(edge.p1.clone(), edge.p2.clone(), pt.clone());
-        if all_pts_on_inside(&data, &tri) {
-            index = Some(i);
-            break;
-        }
-        // swap p1 and p2
-        let tri = Triangle::new(edge.p1.clone(), pt.clone(), edge.p2.clone());
-        if all_pts_on_inside(&data, &tri) {
-            index = Some(i);
-            break;
+        // treat coplanar case as inside.
+        // the normal p1->p2 X p1->p3 points to the inside, orient3d is positive on the other side.
+        if orient3d(p1, p2, p3, pt) > 0.0 {
+            return false;
         }
     }
-    index
+    true
 }
 
-fn all_pts_on_inside(data: &[Point], tri: &Triangle) -> bool {
-    for pt in data {
-        if pt == &tri.p1 || pt == &tri.p2 || pt == &tri.p3 {
-            // skip identical points as in the triangle
+// a face of the hull with more than 3 coplanar points is covered by several triangles, and since
+// the gift wrapping picks any of the coplanar points, those triangles may overlap.
+// each group of coplanar triangles is replaced by a fan over the convex polygon of its vertices,
+// collinear points on the boundary of the polygon are dropped.
+fn retriangulate_coplanar_faces(data: &[Point], triangles: Vec<[usize; 3]>) -> Vec<[usize; 3]> {
+    let mut grouped = vec![false; triangles.len()];
+    let mut result = vec![];
+    for i in 0..triangles.len() {
+        if grouped[i] {
             continue;
         }
-        let (normal_vec, normal_pt) = triangle_normal(&tri);
-        let vec = Vec3D::new(pt.x - normal_pt.x, pt.y - normal_pt.y, pt.z - normal_pt.z);
-        let product = dot_product(&normal_vec, &vec);
-        if product == 0.0 {
-            // eprintln!(
-            //     "({}, {}, {}) and {} are coplanar",
-            //     tri.p1, tri.p2, tri.p3, pt
-            // );
-
-            // treat coplanar case as inside
+        let face = triangles[i];
+        let (p1, p2, p3) = (&data[face[0]], &data[face[1]], &data[face[2]]);
+        let project = projection(p1, p2, p3);
+        let face_sign = orient2d(&project(p1), &project(p2), &project(p3)) > 0.0;
+
+        let mut vertices: Vec<usize> = vec![];
+        for j in i..triangles.len() {
+            let tri = triangles[j];
+            if grouped[j] || tri.iter().any(|&k| orient3d(p1, p2, p3, &data[k]) != 0.0) {
+                continue;
+            }
+            let (q1, q2, q3) = (&data[tri[0]], &data[tri[1]], &data[tri[2]]);
+            if (orient2d(&project(q1), &project(q2), &project(q3)) > 0.0) != face_sign {
+                // same plane but facing the other way, only when all points are coplanar
+                continue;
+            }
+            grouped[j] = true;
+            for k in tri {
+                if !vertices.contains(&k) {
+                    vertices.push(k);
+                }
+            }
         }
-        // denote negative dot product as outside and positive as inside.
-        if product < 0.0 {
-            return false;
+
+        // convex polygon in counter-clockwise order, in the projected plane
+        let polygon = convex_polygon(data, vertices, &project);
+        for k in 1..polygon.len().saturating_sub(1) {
+            if face_sign {
+                result.push([polygon[0], polygon[k], polygon[k + 1]]);
+            } else {
+                result.push([polygon[0], polygon[k + 1], polygon[k]]);
+            }
         }
     }
-    true
+    result
 }
 
-fn triangle_normal(tri: &Triangle) -> (Vec3D, Point) {
-    let a = Vec3D::new(
-        tri.p2.x - tri.p1.x,
-        tri.p2.y - tri.p1.y,
-        tri.p2.z - tri.p1.z,
-    );
-    let b = Vec3D::new(
-        tri.p3.x - tri.p1.x,
-        tri.p3.y - tri.p1.y,
-        tri.p3.z - tri.p1.z,
-    );
-    // p1->p2 X p1->p3
-    (cross_product(&a, &b), tri.p1)
+// the convex polygon of points on the plane of the triangle, as a fan of triangles on each side
+// of the plane so that the hull is closed.
+pub(super) fn polygon_fan(data: &[Point], plane: [usize; 3]) -> Vec<Triangle> {
+    let project = projection(&data[plane[0]], &data[plane[1]], &data[plane[2]]);
+    let polygon = convex_polygon(data, (0..data.len()).collect(), &project);
+    let mut result = vec![];
+    for k in 1..polygon.len() - 1 {
+        let (a, b, c) = (data[polygon[0]], data[polygon[k]], data[polygon[k + 1]]);
+        result.push(Triangle::new(a, b, c));
+        result.push(Triangle::new(a, c, b));
+    }
+    result
 }
 
-fn cross_product(a: &Vec3D, b: &Vec3D) -> Vec3D {
-    Vec3D {
-        x: a.y * b.z - a.z * b.y,
-        y: a.z * b.x - a.x * b.z,
-        z: a.x * b.y - a.y * b.x,
+// project the plane of the triangle onto the axis-aligned plane it is the most parallel to.
+// the areas are compared with the exact predicate, the plane must not project to a line.
+pub(super) fn projection(p1: &Point, p2: &Point, p3: &Point) -> impl Fn(&Point) -> Point {
+    let xy = |pt: &Point| Point::new(pt.x, pt.y, 0.0);
+    let yz = |pt: &Point| Point::new(pt.y, pt.z, 0.0);
+    let zx = |pt: &Point| Point::new(pt.z, pt.x, 0.0);
+    let area_xy = orient2d(&xy(p1), &xy(p2), &xy(p3)).abs();
+    let area_yz = orient2d(&yz(p1), &yz(p2), &yz(p3)).abs();
+    let area_zx = orient2d(&zx(p1), &zx(p2), &zx(p3)).abs();
+    move |pt: &Point| {
+        if area_xy >= area_yz && area_xy >= area_zx {
+            xy(pt)
+        } else if area_yz >= area_zx {
+            yz(pt)
+        } else {
+            zx(pt)
+        }
     }
 }
 
-fn dot_product(a: &Vec3D, b: &Vec3D) -> f64 {
-    a.x * b.x + a.y * b.y + a.z * b.z
+// andrew's monotone chain on the projected points, counter-clockwise without collinear points.
+pub(super) fn convex_polygon(
+    data: &[Point],
+    mut vertices: Vec<usize>,
+    project: &impl Fn(&Point) -> Point,
+) -> Vec<usize> {
+    vertices.sort_by(|&i, &j| {
+        let (pi, pj) = (project(&data[i]), project(&data[j]));
+        pi.x.partial_cmp(&pj.x)
+            .unwrap()
+            .then(pi.y.partial_cmp(&pj.y).unwrap())
+    });
+    let turn = |hull: &[usize], k: usize| {
+        let n = hull.len();
+        orient2d(
+            &project(&data[hull[n - 2]]),
+            &project(&data[hull[n - 1]]),
+            &project(&data[k]),
+        )
+    };
+    let mut hull: Vec<usize> = vec![];
+    // lower hull
+    for &k in &vertices {
+        while hull.len() >= 2 && turn(&hull, k) <= 0.0 {
+            hull.pop();
+        }
+        hull.push(k);
+    }
+    // upper hull
+    let lower_len = hull.len() + 1;
+    for &k in vertices.iter().rev().skip(1) {
+        while hull.len() >= lower_len && turn(&hull, k) <= 0.0 {
+            hull.pop();
+        }
+        hull.push(k);
+    }
+    // the last point is the first one
+    hull.pop();
+    hull
 }
 
 #[cfg(test)]
 mod tests {
     use super::run as jarvis_march;
-    use crate::geometry::{print_pts, sort_points_by_x, Point};
+    use crate::convex_hull_3d::{
+        convex_hull, convex_hull_surface_area, convex_hull_volume, HullMesh,
+    };
+    use crate::error::Error;
+    use crate::geometry::{Point, Triangle};
     use rand::prelude::ThreadRng;
     use rand::{thread_rng, Rng};
 
@@ -208,37 +307,152 @@ mod tests {
             Point::new(0.5234468835170234, 0.9422283433025127, 0.8341827694942268),
             Point::new(0.7662765708922703, 0.571802514850265, 0.30569811331872554),
         ];
-        let result = jarvis_march(test_data);
-        for tri in result {
-            println!("{}, {}, {}", tri.p1, tri.p2, tri.p3);
-        }
+        assert_same_as_quickhull(test_data);
     }
 
     #[test]
     fn test_jarvis_march_3d_rand_10() {
         for _ in 0..100 {
-            let mut rng: ThreadRng = thread_rng();
-            let mut test_data = generate_test_data(10);
-            print_pts(&test_data);
+            assert_same_as_quickhull(generate_test_data(10));
+        }
+    }
+
+    #[test]
+    fn test_jarvis_march_3d_rand_100() {
+        assert_same_as_quickhull(generate_test_data(100));
+    }
 
-            let result = jarvis_march(test_data);
-            for tri in result {
-                println!("{}, {}, {}", tri.p1, tri.p2, tri.p3);
+    const TOLERANCE: f64 = 0.000001;
+
+    // random points are in general position, both hulls have the same triangles
+    fn assert_same_as_quickhull(data: Vec<Point>) {
+        let expected = convex_hull(data.clone()).unwrap();
+        let result = jarvis_march(data).unwrap();
+        assert_eq!(result.len(), expected.len());
+        let volume = convex_hull_volume(&expected);
+        assert!((convex_hull_volume(&result) - volume).abs() < TOLERANCE);
+    }
+
+    // every edge is shared by 2 faces, in opposite direction
+    fn assert_closed(hull: &[Triangle]) {
+        let mesh = HullMesh::from_triangles(hull);
+        let mut edges = std::collections::HashSet::new();
+        for face in &mesh.faces {
+            for (a, b) in [(face[0], face[1]), (face[1], face[2]), (face[2], face[0])] {
+                assert!(edges.insert((a, b)));
             }
-            println!("---\n\n");
+        }
+        for (a, b) in &edges {
+            assert!(edges.contains(&(*b, *a)));
         }
     }
 
     #[test]
-    fn test_jarvis_march_3d_rand_100() {
-        let mut rng: ThreadRng = thread_rng();
-        let mut test_data = generate_test_data(100);
-        print_pts(&test_data);
+    fn test_jarvis_march_3d_box_corners() {
+        // 6 faces with 4 coplanar points each
+        let mut test_data = vec![];
+        for x in [0.0, 2.0] {
+            for y in [0.0, 3.0] {
+                for z in [1.0, 5.0] {
+                    test_data.push(Point::new(x, y, z));
+                }
+            }
+        }
+        test_data.push(Point::new(1.0, 1.0, 2.0));
+        test_data.push(Point::new(1.5, 2.0, 3.0));
+
+        let result = jarvis_march(test_data).unwrap();
+        assert_eq!(result.len(), 12);
+        assert_closed(&result);
+        assert!((convex_hull_volume(&result) - 24.0).abs() < TOLERANCE);
+        assert!((convex_hull_surface_area(&result) - 52.0).abs() < TOLERANCE);
+    }
+
+    #[test]
+    fn test_jarvis_march_3d_grid() {
+        // coplanar points on the faces, and collinear points on the edges of the cube
+        let mut test_data = vec![];
+        for x in 0..4 {
+            for y in 0..4 {
+                for z in 0..4 {
+                    test_data.push(Point::new(x as f64 * 0.1, y as f64 * 0.1, z as f64 * 0.1));
+                }
+            }
+        }
+        let result = jarvis_march(test_data).unwrap();
+        assert_eq!(result.len(), 12);
+        assert_closed(&result);
+        assert!((convex_hull_volume(&result) - 0.027).abs() < TOLERANCE);
+    }
 
-        let result = jarvis_march(test_data);
-        for tri in result {
-            println!("{}, {}, {}", tri.p1, tri.p2, tri.p3);
+    #[test]
+    fn test_jarvis_march_3d_near_coplanar() {
+        // points on the plane z = 0.1 x + 0.3 y, moved off the plane by at most a few ulp,
+        // and an apex above the plane
+        let mut rng = thread_rng();
+        let mut test_data = vec![Point::new(0.5, 0.5, 10.0)];
+        for i in 0..8 {
+            for j in 0..8 {
+                let x = i as f64 / 7.0;
+                let y = j as f64 / 7.0;
+                let z = 0.1 * x + 0.3 * y;
+                let jitter = rng.gen_range(-2..=2) as f64 * f64::EPSILON;
+                test_data.push(Point::new(x, y, z + jitter * z));
+            }
         }
+        let result = jarvis_march(test_data).unwrap();
+        assert_closed(&result);
+        // pyramid with a unit square base (projected on x-y), the apex is 9.8 above the base
+        let volume = convex_hull_volume(&result);
+        assert!((volume - 9.8 / 3.0).abs() < TOLERANCE, "volume: {}", volume);
+    }
+
+    #[test]
+    fn test_jarvis_march_3d_flat() {
+        // all points coplanar, the hull has no volume and covers the polygon on both sides
+        let test_data = vec![
+            Point::new(0.0, 0.0, 1.0),
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(0.0, 2.0, 1.0),
+        ];
+        let result = jarvis_march(test_data.clone()).unwrap();
+        assert_eq!(convex_hull_volume(&result), 0.0);
+        assert!((convex_hull_surface_area(&result) - 3.0).abs() < 0.000001);
+        assert_eq!(HullMesh::from_triangles(&result).vertices.len(), 4);
+
+        // a 5 x 5 grid on [0, 4] x [0, 4], the inner points and the points on the edges are not
+        // vertices of the hull
+        let mut test_data = vec![];
+        for i in 0..5 {
+            for j in 0..5 {
+                test_data.push(Point::new(i as f64, j as f64, 1.0));
+            }
+        }
+        let result = jarvis_march(test_data).unwrap();
+        assert_eq!(convex_hull_volume(&result), 0.0);
+        assert!((convex_hull_surface_area(&result) - 32.0).abs() < 0.000001);
+        let mut vertices: Vec<(f64, f64)> = HullMesh::from_triangles(&result)
+            .vertices
+            .iter()
+            .map(|pt| (pt.x, pt.y))
+            .collect();
+        vertices.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(
+            vertices,
+            vec![(0.0, 0.0), (0.0, 4.0), (4.0, 0.0), (4.0, 4.0)]
+        );
+    }
+
+    #[test]
+    fn test_jarvis_march_3d_collinear() {
+        let test_data = vec![
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(1.0, 2.0, 3.0),
+            Point::new(2.0, 4.0, 6.0),
+            Point::new(3.0, 6.0, 9.0),
+        ];
+        assert!(matches!(jarvis_march(test_data), Err(Error::Degenerate(_))));
     }
 
     fn generate_test_data(count: usize) -> Vec<Point> {
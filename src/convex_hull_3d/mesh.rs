@@ -0,0 +1,141 @@
+use super::volume::tetrahedron_volume;
+use crate::geometry::{Point, Triangle};
+use crate::predicates::orient3d;
+use std::collections::HashMap;
+
+// indexed closed triangle mesh of a 3D hull, or of an alpha shape that is not convex.
+// each vertex is stored once, faces refer to the vertices by index and are wound counter-clockwise
+// seen from outside of the mesh (the normal p1->p2 X p1->p3 points outward).
+#[derive(Clone, Default)]
+pub struct HullMesh {
+    pub vertices: Vec<Point>,
+    pub faces: Vec<[u32; 3]>,
+}
+
+impl HullMesh {
+    // build the mesh from the triangles of a convex hull, the winding of the input does not matter.
+    pub fn from_triangles(hull: &[Triangle]) -> Self {
+        let mut mesh = HullMesh::default();
+        let mut indices: HashMap<(u64, u64, u64), u32> = HashMap::new();
+        for tri in hull {
+            let mut face = [0u32; 3];
+            for (i, pt) in [tri.p1, tri.p2, tri.p3].iter().enumerate() {
+                let key = (pt.x.to_bits(), pt.y.to_bits(), pt.z.to_bits());
+                face[i] = *indices.entry(key).or_insert_with(|| {
+                    mesh.vertices.push(*pt);
+                    (mesh.vertices.len() - 1) as u32
+                });
+            }
+            mesh.faces.push(face);
+        }
+        mesh.orient_outward();
+        mesh
+    }
+
+    pub fn triangles(&self) -> Vec<Triangle> {
+        self.faces
+            .iter()
+            .map(|face| {
+                Triangle::new(
+                    self.vertices[face[0] as usize],
+                    self.vertices[face[1] as usize],
+                    self.vertices[face[2] as usize],
+                )
+            })
+            .collect()
+    }
+
+    // the signed volumes of the tetrahedra between the faces and the first vertex add up to the
+    // enclosed volume (divergence theorem), the mesh does not have to be convex.
+    pub fn volume(&self) -> f64 {
+        let Some(origin) = self.vertices.first() else {
+            return 0.0;
+        };
+        self.faces
+            .iter()
+            .map(|face| {
+                let [p1, p2, p3] = face.map(|i| &self.vertices[i as usize]);
+                tetrahedron_volume(p1, p2, p3, origin)
+            })
+            .sum()
+    }
+
+    // the centroid of the vertices is inside of the convex hull,
+    // flip the faces whose normal points toward it.
+    fn orient_outward(&mut self) {
+        if self.vertices.is_empty() {
+            return;
+        }
+        let count = self.vertices.len() as f64;
+        let cx = self.vertices.iter().map(|pt| pt.x).sum::<f64>() / count;
+        let cy = self.vertices.iter().map(|pt| pt.y).sum::<f64>() / count;
+        let cz = self.vertices.iter().map(|pt| pt.z).sum::<f64>() / count;
+        let center = Point::new(cx, cy, cz);
+        for face in self.faces.iter_mut() {
+            let p1 = &self.vertices[face[0] as usize];
+            let p2 = &self.vertices[face[1] as usize];
+            let p3 = &self.vertices[face[2] as usize];
+            // the center must be below the face
+            if orient3d(p1, p2, p3, &center) < 0.0 {
+                face.swap(1, 2);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HullMesh;
+    use crate::convex_hull_3d::convex_hull;
+    use crate::geometry::{Point, Triangle};
+
+    #[test]
+    fn test_hull_mesh_tetrahedron() {
+        let o = Point::new(0.0, 0.0, 0.0);
+        let a = Point::new(1.0, 0.0, 0.0);
+        let b = Point::new(0.0, 1.0, 0.0);
+        let c = Point::new(0.0, 0.0, 1.0);
+        // mixed winding
+        let hull = vec![
+            Triangle::new(o, a, b),
+            Triangle::new(o, a, c),
+            Triangle::new(o, c, b),
+            Triangle::new(a, b, c),
+        ];
+        let mesh = HullMesh::from_triangles(&hull);
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.faces.len(), 4);
+        // bottom face (z = 0) normal must point to -z
+        let bottom = mesh.faces[0];
+        let p1 = mesh.vertices[bottom[0] as usize];
+        let p2 = mesh.vertices[bottom[1] as usize];
+        let p3 = mesh.vertices[bottom[2] as usize];
+        let nz = (p2.x - p1.x) * (p3.y - p1.y) - (p2.y - p1.y) * (p3.x - p1.x);
+        assert!(nz < 0.0);
+        assert!((mesh.volume() - 1.0 / 6.0).abs() < 0.000001);
+    }
+
+    #[test]
+    fn test_hull_mesh_consistent_winding() {
+        let mut data = vec![];
+        for i in 0..200 {
+            let t = i as f64 * 0.1;
+            data.push(Point::new(
+                t.cos() * (1.0 + t),
+                t.sin(),
+                (t * 0.3).cos() * 2.0,
+            ));
+        }
+        let mesh = HullMesh::from_triangles(&convex_hull(data).unwrap());
+        // every directed edge appears once, and its reverse appears once
+        let mut edges = std::collections::HashSet::new();
+        for face in &mesh.faces {
+            for (a, b) in [(face[0], face[1]), (face[1], face[2]), (face[2], face[0])] {
+                assert!(edges.insert((a, b)));
+            }
+        }
+        for (a, b) in &edges {
+            assert!(edges.contains(&(*b, *a)));
+        }
+    }
+}
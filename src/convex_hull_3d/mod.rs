@@ -1,9 +1,37 @@
+mod area;
 mod chan;
 mod jarvis_march;
+mod mesh;
+mod quickhull;
 mod volume;
 
+use crate::delaunay::delaunay_3d;
+use crate::error::Result;
 use crate::geometry::{Point, Triangle};
 
-pub fn convex_hull(mut data: Vec<Point>) -> Vec<Triangle> {
-    chan::run(data)
+pub use area::convex_hull_surface_area;
+pub use mesh::HullMesh;
+pub use volume::convex_hull_volume;
+
+// algorithms to compute the 3D convex hull.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    JarvisMarch,
+    Chan,
+    QuickHull,
+    // the boundary of the Delaunay tetrahedralization, a cross-check of the other algorithms
+    Delaunay,
+}
+
+pub fn convex_hull(data: Vec<Point>) -> Result<Vec<Triangle>> {
+    convex_hull_with(data, Algorithm::QuickHull)
+}
+
+pub fn convex_hull_with(data: Vec<Point>, algorithm: Algorithm) -> Result<Vec<Triangle>> {
+    match algorithm {
+        Algorithm::JarvisMarch => jarvis_march::run(data),
+        Algorithm::Chan => chan::run(data),
+        Algorithm::QuickHull => quickhull::run(data),
+        Algorithm::Delaunay => Ok(delaunay_3d(&data)?.hull(&data)),
+    }
 }
@@ -0,0 +1,593 @@
+use super::jarvis_march;
+use crate::error::Result;
+use crate::geometry::{Point, Triangle, Vec3D};
+use crate::predicates::{collinear3d, orient3d};
+use std::collections::HashMap;
+
+// QuickHull in 3D, http://www.cs.ubc.ca/~lloyd/java/quickhull3d.html
+// and Barber, Dobkin, Huhdanpaa, "The Quickhull Algorithm for Convex Hulls".
+//
+// the hull is kept as a half-edge mesh of triangles, each face owns the set of points that are
+// outside of it (the conflict list). The farthest outside point of a face is added to the hull by
+// removing all the faces visible from it and connecting the horizon to the point.
+// Expected running time is O(n log n).
+
+// points that do not span an area (none, all the same or collinear) have an empty hull.
+pub fn run(data: Vec<Point>) -> Result<Vec<Triangle>> {
+    let mut hull = QuickHull::new(data);
+    if !hull.init_simplex() {
+        // all points are coplanar (or collinear), the hull is flat.
+        return Ok(flat_hull(&hull.points));
+    }
+    hull.build();
+    Ok(hull.triangles())
+}
+
+// the convex polygon of coplanar points, as a fan of triangles on each side of the plane so that
+// the hull is closed. empty if the points are collinear.
+fn flat_hull(points: &[Point]) -> Vec<Triangle> {
+    // the points are deduplicated, points[0] and points[1] make a line
+    let third = (2..points.len()).find(|&i| !collinear3d(&points[0], &points[1], &points[i]));
+    let third = match third {
+        None => return vec![],
+        Some(val) => val,
+    };
+    jarvis_march::polygon_fan(points, [0, 1, third])
+}
+
+// half edge pointing to the vertex `head`, the tail is the head of the previous edge.
+#[derive(Copy, Clone)]
+struct HalfEdge {
+    head: usize,
+    twin: usize,
+    next: usize,
+    face: usize,
+}
+
+struct Face {
+    // one of the 3 half edges of the face
+    edge: usize,
+    // outward normal, the face is counter-clockwise seen from outside
+    normal: Vec3D,
+    offset: f64,
+    // points outside of the face, not yet in the hull
+    outside: Vec<usize>,
+    alive: bool,
+}
+
+struct QuickHull {
+    points: Vec<Point>,
+    edges: Vec<HalfEdge>,
+    faces: Vec<Face>,
+    // faces that may have outside points to process
+    pending: Vec<usize>,
+}
+
+impl QuickHull {
+    fn new(mut data: Vec<Point>) -> Self {
+        data.sort_by(|pt1, pt2| {
+            pt1.x
+                .partial_cmp(&pt2.x)
+                .unwrap()
+                .then(pt1.y.partial_cmp(&pt2.y).unwrap())
+                .then(pt1.z.partial_cmp(&pt2.z).unwrap())
+        });
+        data.dedup_by(|pt1, pt2| pt1.x == pt2.x && pt1.y == pt2.y && pt1.z == pt2.z);
+        QuickHull {
+            points: data,
+            edges: vec![],
+            faces: vec![],
+            pending: vec![],
+        }
+    }
+
+    // build the initial tetrahedron from the extreme points, and assign all other points to it.
+    // returns false if the points does not span a volume.
+    fn init_simplex(&mut self) -> bool {
+        if self.points.len() < 4 {
+            return false;
+        }
+        let pts = &self.points;
+        // extreme points along x, y, z
+        let mut extremes = [0usize; 6];
+        for (i, pt) in pts.iter().enumerate() {
+            if pt.x < pts[extremes[0]].x {
+                extremes[0] = i;
+            }
+            if pt.x > pts[extremes[1]].x {
+                extremes[1] = i;
+            }
+            if pt.y < pts[extremes[2]].y {
+                extremes[2] = i;
+            }
+            if pt.y > pts[extremes[3]].y {
+                extremes[3] = i;
+            }
+            if pt.z < pts[extremes[4]].z {
+                extremes[4] = i;
+            }
+            if pt.z > pts[extremes[5]].z {
+                extremes[5] = i;
+            }
+        }
+        // the axis with the largest extent gives the 1st and 2nd vertex
+        let mut v0 = extremes[0];
+        let mut v1 = extremes[1];
+        let mut max_extent = pts[v1].x - pts[v0].x;
+        if pts[extremes[3]].y - pts[extremes[2]].y > max_extent {
+            v0 = extremes[2];
+            v1 = extremes[3];
+            max_extent = pts[v1].y - pts[v0].y;
+        }
+        if pts[extremes[5]].z - pts[extremes[4]].z > max_extent {
+            v0 = extremes[4];
+            v1 = extremes[5];
+        }
+
+        // 3rd vertex is the farthest from the line v0->v1
+        let line = sub(&pts[v1], &pts[v0]);
+        let mut v2 = v0;
+        let mut max_dist = 0.0f64;
+        for (i, pt) in pts.iter().enumerate() {
+            let dist = norm(&cross_product(&line, &sub(pt, &pts[v0])));
+            if dist > max_dist {
+                max_dist = dist;
+                v2 = i;
+            }
+        }
+        if collinear3d(&pts[v0], &pts[v1], &pts[v2]) {
+            // the farthest point is only a guess in f64, look for any point off the line
+            match (0..pts.len()).find(|&i| !collinear3d(&pts[v0], &pts[v1], &pts[i])) {
+                Some(val) => v2 = val,
+                None => return false,
+            }
+        }
+
+        // 4th vertex is the farthest from the plane v0, v1, v2
+        let normal = normalize(&cross_product(&line, &sub(&pts[v2], &pts[v0])));
+        let offset = dot_product(&normal, &to_vec(&pts[v0]));
+        let mut v3 = v0;
+        let mut max_dist = 0.0f64;
+        for (i, pt) in pts.iter().enumerate() {
+            let dist = (dot_product(&normal, &to_vec(pt)) - offset).abs();
+            if dist > max_dist {
+                max_dist = dist;
+                v3 = i;
+            }
+        }
+        if orient3d(&pts[v0], &pts[v1], &pts[v2], &pts[v3]) == 0.0 {
+            match (0..pts.len()).find(|&i| orient3d(&pts[v0], &pts[v1], &pts[v2], &pts[i]) != 0.0) {
+                Some(val) => v3 = val,
+                None => return false,
+            }
+        }
+
+        // keep the faces counter-clockwise seen from outside
+        let triangles = if orient3d(&pts[v0], &pts[v1], &pts[v2], &pts[v3]) > 0.0 {
+            [[v0, v1, v2], [v0, v3, v1], [v1, v3, v2], [v2, v3, v0]]
+        } else {
+            [[v0, v2, v1], [v0, v1, v3], [v1, v2, v3], [v2, v0, v3]]
+        };
+        let mut twins: HashMap<(usize, usize), usize> = HashMap::new();
+        for tri in triangles.iter() {
+            let face = self.add_face(tri[0], tri[1], tri[2]);
+            let edge = self.faces[face].edge;
+            for e in [
+                edge,
+                self.edges[edge].next,
+                self.edges[self.edges[edge].next].next,
+            ] {
+                twins.insert((self.tail(e), self.edges[e].head), e);
+            }
+        }
+        for e in 0..self.edges.len() {
+            let twin = twins[&(self.edges[e].head, self.tail(e))];
+            self.edges[e].twin = twin;
+        }
+
+        let all_pts: Vec<usize> = (0..self.points.len())
+            .filter(|i| ![v0, v1, v2, v3].contains(i))
+            .collect();
+        let new_faces: Vec<usize> = (0..self.faces.len()).collect();
+        self.assign_points(&all_pts, &new_faces);
+        true
+    }
+
+    fn build(&mut self) {
+        while let Some(face) = self.pending.pop() {
+            if !self.faces[face].alive || self.faces[face].outside.is_empty() {
+                continue;
+            }
+            let eye = self.farthest_outside_point(face);
+            self.add_point(eye, face);
+        }
+    }
+
+    fn farthest_outside_point(&self, face: usize) -> usize {
+        let mut selected = self.faces[face].outside[0];
+        let mut max_dist = f64::MIN;
+        for &i in &self.faces[face].outside {
+            let dist = self.distance(face, i);
+            if dist > max_dist {
+                max_dist = dist;
+                selected = i;
+            }
+        }
+        selected
+    }
+
+    fn add_point(&mut self, eye: usize, face: usize) {
+        let mut horizon: Vec<usize> = vec![];
+        let mut orphans: Vec<usize> = vec![];
+        self.compute_horizon(eye, None, face, &mut horizon, &mut orphans);
+
+        // connect every horizon edge to the eye point, the horizon is in counter-clockwise order
+        let mut new_faces: Vec<usize> = Vec::with_capacity(horizon.len());
+        for &e in &horizon {
+            let tail = self.tail(e);
+            let head = self.edges[e].head;
+            let new_face = self.add_face(tail, head, eye);
+            let new_edge = self.faces[new_face].edge;
+            let outer = self.edges[e].twin;
+            self.edges[new_edge].twin = outer;
+            self.edges[outer].twin = new_edge;
+            new_faces.push(new_face);
+        }
+        // link the side edges of the neighboring new faces
+        for i in 0..new_faces.len() {
+            let curr = self.faces[new_faces[i]].edge;
+            let next_face = new_faces[(i + 1) % new_faces.len()];
+            let next = self.faces[next_face].edge;
+            // head -> eye of the current face, eye -> tail of the next face
+            let curr_side = self.edges[curr].next;
+            let next_side = self.edges[self.edges[next].next].next;
+            self.edges[curr_side].twin = next_side;
+            self.edges[next_side].twin = curr_side;
+        }
+
+        orphans.retain(|&i| i != eye);
+        self.assign_points(&orphans, &new_faces);
+    }
+
+    // remove the faces visible from the eye point, starting at `face`.
+    // the edges of the removed faces whose neighbor is not visible form the horizon.
+    fn compute_horizon(
+        &mut self,
+        eye: usize,
+        crossed_edge: Option<usize>,
+        face: usize,
+        horizon: &mut Vec<usize>,
+        orphans: &mut Vec<usize>,
+    ) {
+        self.faces[face].alive = false;
+        orphans.append(&mut self.faces[face].outside);
+
+        let start = match crossed_edge {
+            None => self.faces[face].edge,
+            Some(val) => self.edges[val].next,
+        };
+        let mut e = start;
+        loop {
+            let twin = self.edges[e].twin;
+            let opposite = self.edges[twin].face;
+            if self.faces[opposite].alive {
+                if self.is_outside(opposite, eye) {
+                    self.compute_horizon(eye, Some(twin), opposite, horizon, orphans);
+                } else {
+                    horizon.push(e);
+                }
+            }
+            e = self.edges[e].next;
+            if e == start {
+                break;
+            }
+        }
+    }
+
+    // give each point to the 1st face it is outside of, points inside all faces are dropped.
+    fn assign_points(&mut self, pts: &[usize], faces: &[usize]) {
+        for &i in pts {
+            for &face in faces {
+                if self.is_outside(face, i) {
+                    if self.faces[face].outside.is_empty() {
+                        self.pending.push(face);
+                    }
+                    self.faces[face].outside.push(i);
+                    break;
+                }
+            }
+        }
+    }
+
+    // create the face (a, b, c) with its 3 half edges, twins are left to the caller.
+    fn add_face(&mut self, a: usize, b: usize, c: usize) -> usize {
+        let face = self.faces.len();
+        let e0 = self.edges.len();
+        for (i, head) in [b, c, a].iter().enumerate() {
+            self.edges.push(HalfEdge {
+                head: *head,
+                twin: usize::MAX,
+                next: e0 + (i + 1) % 3,
+                face,
+            });
+        }
+        let pa = &self.points[a];
+        let normal = normalize(&cross_product(
+            &sub(&self.points[b], pa),
+            &sub(&self.points[c], pa),
+        ));
+        let offset = dot_product(&normal, &to_vec(pa));
+        self.faces.push(Face {
+            edge: e0,
+            normal,
+            offset,
+            outside: vec![],
+            alive: true,
+        });
+        face
+    }
+
+    fn tail(&self, edge: usize) -> usize {
+        // faces are triangles, the previous edge is next of next
+        let prev = self.edges[self.edges[edge].next].next;
+        self.edges[prev].head
+    }
+
+    // exact test of the point being strictly outside of the face.
+    fn is_outside(&self, face: usize, pt: usize) -> bool {
+        let e0 = self.faces[face].edge;
+        let e1 = self.edges[e0].next;
+        // the face is counter-clockwise seen from outside
+        orient3d(
+            &self.points[self.tail(e0)],
+            &self.points[self.edges[e0].head],
+            &self.points[self.edges[e1].head],
+            &self.points[pt],
+        ) < 0.0
+    }
+
+    // signed distance of the point to the plane of the face, positive is outside.
+    // only used to pick the farthest point, not to decide if a point is outside.
+    fn distance(&self, face: usize, pt: usize) -> f64 {
+        let face = &self.faces[face];
+        dot_product(&face.normal, &to_vec(&self.points[pt])) - face.offset
+    }
+
+    fn triangles(&self) -> Vec<Triangle> {
+        let mut result = vec![];
+        for face in self.faces.iter().filter(|face| face.alive) {
+            let e0 = face.edge;
+            let e1 = self.edges[e0].next;
+            result.push(Triangle::new(
+                self.points[self.tail(e0)],
+                self.points[self.edges[e0].head],
+                self.points[self.edges[e1].head],
+            ));
+        }
+        result
+    }
+}
+
+fn to_vec(pt: &Point) -> Vec3D {
+    Vec3D::new(pt.x, pt.y, pt.z)
+}
+
+fn sub(a: &Point, b: &Point) -> Vec3D {
+    Vec3D::new(a.x - b.x, a.y - b.y, a.z - b.z)
+}
+
+fn cross_product(a: &Vec3D, b: &Vec3D) -> Vec3D {
+    Vec3D {
+        x: a.y * b.z - a.z * b.y,
+        y: a.z * b.x - a.x * b.z,
+        z: a.x * b.y - a.y * b.x,
+    }
+}
+
+fn dot_product(a: &Vec3D, b: &Vec3D) -> f64 {
+    a.x * b.x + a.y * b.y + a.z * b.z
+}
+
+fn norm(a: &Vec3D) -> f64 {
+    dot_product(a, a).sqrt()
+}
+
+fn normalize(a: &Vec3D) -> Vec3D {
+    let len = norm(a);
+    Vec3D::new(a.x / len, a.y / len, a.z / len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::run as quickhull;
+    use crate::convex_hull_3d::{convex_hull_surface_area, convex_hull_volume, jarvis_march};
+    use crate::geometry::{Point, Triangle};
+    use rand::prelude::ThreadRng;
+    use rand::{thread_rng, Rng};
+    use std::collections::HashMap;
+
+    const TOLERANCE: f64 = 0.000001;
+
+    // every edge of a closed hull is shared by exactly 2 triangles, in opposite direction.
+    fn assert_closed(hull: &[Triangle]) {
+        let key = |pt: &Point| (pt.x.to_bits(), pt.y.to_bits(), pt.z.to_bits());
+        let mut edges = HashMap::new();
+        for tri in hull {
+            for (a, b) in [(tri.p1, tri.p2), (tri.p2, tri.p3), (tri.p3, tri.p1)] {
+                *edges.entry((key(&a), key(&b))).or_insert(0) += 1;
+            }
+        }
+        for ((a, b), count) in &edges {
+            assert_eq!(*count, 1);
+            assert_eq!(edges.get(&(*b, *a)), Some(&1));
+        }
+    }
+
+    // all points are inside (or on) every face, faces are counter-clockwise seen from outside.
+    fn assert_contains(hull: &[Triangle], data: &[Point]) {
+        for tri in hull {
+            let (ax, ay, az) = (
+                tri.p2.x - tri.p1.x,
+                tri.p2.y - tri.p1.y,
+                tri.p2.z - tri.p1.z,
+            );
+            let (bx, by, bz) = (
+                tri.p3.x - tri.p1.x,
+                tri.p3.y - tri.p1.y,
+                tri.p3.z - tri.p1.z,
+            );
+            let (nx, ny, nz) = (ay * bz - az * by, az * bx - ax * bz, ax * by - ay * bx);
+            for pt in data {
+                let d = nx * (pt.x - tri.p1.x) + ny * (pt.y - tri.p1.y) + nz * (pt.z - tri.p1.z);
+                assert!(d <= TOLERANCE, "{} is outside", pt);
+            }
+        }
+    }
+
+    #[test]
+    fn test_quickhull_tetrahedron() {
+        let data = vec![
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(0.0, 0.0, 1.0),
+            Point::new(0.1, 0.1, 0.1),
+        ];
+        let hull = quickhull(data.clone()).unwrap();
+        assert_eq!(hull.len(), 4);
+        assert_closed(&hull);
+        assert_contains(&hull, &data);
+        assert!((convex_hull_volume(&hull) - 1.0 / 6.0).abs() < TOLERANCE);
+    }
+
+    #[test]
+    fn test_quickhull_cube_grid() {
+        // many coplanar points on the faces of the cube
+        let mut data = vec![];
+        for x in 0..5 {
+            for y in 0..5 {
+                for z in 0..5 {
+                    data.push(Point::new(x as f64 / 4.0, y as f64 / 4.0, z as f64 / 4.0));
+                }
+            }
+        }
+        let hull = quickhull(data.clone()).unwrap();
+        assert_closed(&hull);
+        assert_contains(&hull, &data);
+        assert!((convex_hull_volume(&hull) - 1.0).abs() < TOLERANCE);
+    }
+
+    #[test]
+    fn test_quickhull_rand_compare_jarvis_march() {
+        for _ in 0..10 {
+            let data = generate_test_data(50);
+            let hull = quickhull(data.clone()).unwrap();
+            assert_closed(&hull);
+            assert_contains(&hull, &data);
+            let expected = jarvis_march::run(data).unwrap();
+            assert_eq!(hull.len(), expected.len());
+            let volume = convex_hull_volume(&hull);
+            let expected_volume = convex_hull_volume(&expected);
+            assert!((volume - expected_volume).abs() < TOLERANCE);
+        }
+    }
+
+    #[test]
+    fn test_quickhull_rand_10000() {
+        let data = generate_test_data(10000);
+        let hull = quickhull(data.clone()).unwrap();
+        assert_closed(&hull);
+        assert_contains(&hull, &data);
+        // triangulated hull, F = 2V - 4
+        let mut vertices: Vec<Point> = vec![];
+        for tri in &hull {
+            for pt in [tri.p1, tri.p2, tri.p3] {
+                if !vertices
+                    .iter()
+                    .any(|v| v.x == pt.x && v.y == pt.y && v.z == pt.z)
+                {
+                    vertices.push(pt);
+                }
+            }
+        }
+        assert_eq!(hull.len(), 2 * vertices.len() - 4);
+    }
+
+    #[test]
+    fn test_quickhull_near_coplanar() {
+        // points on the plane z = 0.1 x + 0.3 y, moved off the plane by at most a few ulp,
+        // and an apex above the plane
+        let mut rng = thread_rng();
+        let mut data = vec![Point::new(0.5, 0.5, 10.0)];
+        for i in 0..8 {
+            for j in 0..8 {
+                let x = i as f64 / 7.0;
+                let y = j as f64 / 7.0;
+                let z = 0.1 * x + 0.3 * y;
+                let jitter = rng.gen_range(-2..=2) as f64 * f64::EPSILON;
+                data.push(Point::new(x, y, z + jitter * z));
+            }
+        }
+        let hull = quickhull(data.clone()).unwrap();
+        assert_closed(&hull);
+        assert_contains(&hull, &data);
+        assert!((convex_hull_volume(&hull) - 9.8 / 3.0).abs() < TOLERANCE);
+    }
+
+    #[test]
+    fn test_quickhull_degenerated() {
+        // no area, no hull, whatever the number of points
+        assert!(quickhull(vec![]).unwrap().is_empty());
+        let duplicated = vec![Point::new(1.0, 2.0, 3.0); 10];
+        assert!(quickhull(duplicated).unwrap().is_empty());
+        for count in [2, 3, 4, 20] {
+            let collinear = (0..count)
+                .map(|i| Point::new(i as f64, i as f64, i as f64))
+                .collect();
+            assert!(quickhull(collinear).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_quickhull_flat() {
+        // a tilted square with points inside and on its edges, a fan of 2 triangles on each side
+        let mut data = vec![];
+        for i in 0..5 {
+            for j in 0..5 {
+                let (x, y) = (i as f64 / 4.0, j as f64 / 4.0);
+                data.push(Point::new(x, y, 0.5 * x + 0.25 * y));
+            }
+        }
+        let hull = quickhull(data.clone()).unwrap();
+        assert_eq!(hull.len(), 4);
+        // closed, the diagonal of the fan is an edge of both sides
+        let key = |pt: &Point| (pt.x.to_bits(), pt.y.to_bits(), pt.z.to_bits());
+        let mut edges = HashMap::new();
+        for tri in &hull {
+            for (a, b) in [(tri.p1, tri.p2), (tri.p2, tri.p3), (tri.p3, tri.p1)] {
+                *edges.entry((key(&a), key(&b))).or_insert(0) += 1;
+                *edges.entry((key(&b), key(&a))).or_insert(0) -= 1;
+            }
+        }
+        assert!(edges.values().all(|count| *count == 0));
+        assert_eq!(convex_hull_volume(&hull), 0.0);
+        // both sides of the square
+        let area = (1.0f64 + 0.25 + 0.0625).sqrt();
+        assert!((convex_hull_surface_area(&hull) - 2.0 * area).abs() < TOLERANCE);
+        // a single triangle
+        let hull = quickhull(data[..6].to_vec()).unwrap();
+        assert_eq!(hull.len(), 2);
+    }
+
+    fn generate_test_data(count: usize) -> Vec<Point> {
+        let mut rng: ThreadRng = thread_rng();
+        let mut test_data = vec![];
+        while test_data.len() < count {
+            let x: f64 = rng.gen();
+            let y: f64 = rng.gen();
+            let z: f64 = rng.gen();
+            test_data.push(Point::new(x, y, z));
+        }
+        test_data
+    }
+}
@@ -1 +1,136 @@
-// volume
+use crate::geometry::{Point, Triangle};
+
+// compute the volume enclosed by the triangles of a 3D convex hull.
+// Note: the triangles are not required to have a consistent winding (clockwise or counter-clockwise),
+// each triangle forms a tetrahedron with a point inside the hull, and the absolute volume of each
+// tetrahedron is summed up.
+pub fn convex_hull_volume(hull: &[Triangle]) -> f64 {
+    if hull.is_empty() {
+        return 0.0;
+    }
+    let center = hull_center(hull);
+    let mut volume = 0.0f64;
+    for tri in hull {
+        volume += tetrahedron_volume(&tri.p1, &tri.p2, &tri.p3, &center).abs();
+    }
+    volume
+}
+
+// average of all the triangle vertices, this is always inside the hull since the hull is convex.
+fn hull_center(hull: &[Triangle]) -> Point {
+    let mut x = 0.0f64;
+    let mut y = 0.0f64;
+    let mut z = 0.0f64;
+    for tri in hull {
+        x += tri.p1.x + tri.p2.x + tri.p3.x;
+        y += tri.p1.y + tri.p2.y + tri.p3.y;
+        z += tri.p1.z + tri.p2.z + tri.p3.z;
+    }
+    let count = (hull.len() * 3) as f64;
+    Point::new(x / count, y / count, z / count)
+}
+
+// signed volume of the tetrahedron, 1/6 of the scalar triple product (p1-p4) . ((p2-p4) X (p3-p4))
+pub(super) fn tetrahedron_volume(p1: &Point, p2: &Point, p3: &Point, p4: &Point) -> f64 {
+    let (ax, ay, az) = (p1.x - p4.x, p1.y - p4.y, p1.z - p4.z);
+    let (bx, by, bz) = (p2.x - p4.x, p2.y - p4.y, p2.z - p4.z);
+    let (cx, cy, cz) = (p3.x - p4.x, p3.y - p4.y, p3.z - p4.z);
+    (ax * (by * cz - bz * cy) + ay * (bz * cx - bx * cz) + az * (bx * cy - by * cx)) / 6.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::convex_hull_volume;
+    use crate::convex_hull_3d::jarvis_march;
+    use crate::geometry::{Point, Triangle};
+    use rand::{thread_rng, Rng};
+
+    const TOLERANCE: f64 = 0.000001;
+
+    fn unit_cube() -> Vec<Triangle> {
+        let p = |x: f64, y: f64, z: f64| Point::new(x, y, z);
+        vec![
+            // bottom, z = 0
+            Triangle::new(p(0.0, 0.0, 0.0), p(1.0, 0.0, 0.0), p(1.0, 1.0, 0.0)),
+            Triangle::new(p(0.0, 0.0, 0.0), p(1.0, 1.0, 0.0), p(0.0, 1.0, 0.0)),
+            // top, z = 1
+            Triangle::new(p(0.0, 0.0, 1.0), p(1.0, 0.0, 1.0), p(1.0, 1.0, 1.0)),
+            Triangle::new(p(0.0, 0.0, 1.0), p(0.0, 1.0, 1.0), p(1.0, 1.0, 1.0)),
+            // front, y = 0
+            Triangle::new(p(0.0, 0.0, 0.0), p(1.0, 0.0, 1.0), p(1.0, 0.0, 0.0)),
+            Triangle::new(p(0.0, 0.0, 0.0), p(0.0, 0.0, 1.0), p(1.0, 0.0, 1.0)),
+            // back, y = 1
+            Triangle::new(p(0.0, 1.0, 0.0), p(1.0, 1.0, 0.0), p(1.0, 1.0, 1.0)),
+            Triangle::new(p(0.0, 1.0, 1.0), p(0.0, 1.0, 0.0), p(1.0, 1.0, 1.0)),
+            // left, x = 0
+            Triangle::new(p(0.0, 0.0, 0.0), p(0.0, 1.0, 0.0), p(0.0, 1.0, 1.0)),
+            Triangle::new(p(0.0, 0.0, 0.0), p(0.0, 0.0, 1.0), p(0.0, 1.0, 1.0)),
+            // right, x = 1
+            Triangle::new(p(1.0, 0.0, 0.0), p(1.0, 1.0, 1.0), p(1.0, 1.0, 0.0)),
+            Triangle::new(p(1.0, 0.0, 0.0), p(1.0, 0.0, 1.0), p(1.0, 1.0, 1.0)),
+        ]
+    }
+
+    #[test]
+    fn test_volume_unit_cube() {
+        let volume = convex_hull_volume(&unit_cube());
+        assert!((volume - 1.0).abs() < TOLERANCE, "volume: {}", volume);
+    }
+
+    #[test]
+    fn test_volume_scaled_cube() {
+        let hull: Vec<Triangle> = unit_cube()
+            .iter()
+            .map(|tri| {
+                let scale = |pt: &Point| Point::new(pt.x * 2.0 + 5.0, pt.y * 3.0 - 1.0, pt.z * 0.5);
+                Triangle::new(scale(&tri.p1), scale(&tri.p2), scale(&tri.p3))
+            })
+            .collect();
+        let volume = convex_hull_volume(&hull);
+        assert!((volume - 3.0).abs() < TOLERANCE, "volume: {}", volume);
+    }
+
+    #[test]
+    fn test_volume_tetrahedron() {
+        let o = Point::new(0.0, 0.0, 0.0);
+        let a = Point::new(1.0, 0.0, 0.0);
+        let b = Point::new(0.0, 1.0, 0.0);
+        let c = Point::new(0.0, 0.0, 1.0);
+        // mixed winding
+        let hull = vec![
+            Triangle::new(o, a, b),
+            Triangle::new(o, a, c),
+            Triangle::new(o, c, b),
+            Triangle::new(a, b, c),
+        ];
+        let volume = convex_hull_volume(&hull);
+        assert!((volume - 1.0 / 6.0).abs() < TOLERANCE, "volume: {}", volume);
+    }
+
+    #[test]
+    fn test_volume_empty() {
+        assert_eq!(convex_hull_volume(&[]), 0.0);
+    }
+
+    #[test]
+    fn test_volume_jarvis_march_tetrahedron() {
+        // corners of a tetrahedron and random points strictly inside of it
+        let mut rng = thread_rng();
+        let mut data = vec![
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(2.0, 0.0, 0.0),
+            Point::new(0.0, 2.0, 0.0),
+            Point::new(0.0, 0.0, 2.0),
+        ];
+        while data.len() < 50 {
+            let x: f64 = rng.gen_range(0.01..0.6);
+            let y: f64 = rng.gen_range(0.01..0.6);
+            let z: f64 = rng.gen_range(0.01..0.6);
+            data.push(Point::new(x, y, z));
+        }
+        let hull = jarvis_march::run(data).unwrap();
+        assert_eq!(hull.len(), 4);
+        let volume = convex_hull_volume(&hull);
+        assert!((volume - 8.0 / 6.0).abs() < TOLERANCE, "volume: {}", volume);
+    }
+}
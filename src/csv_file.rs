@@ -0,0 +1,67 @@
+use crate::error::{Error, Result};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+// write the header, then one line per row.
+pub fn write_lines(path: &str, header: &str, rows: impl IntoIterator<Item = String>) -> Result<()> {
+    let file = match File::create(path) {
+        Ok(val) => val,
+        Err(err) => return Err(Error::Io(String::from(path), err)),
+    };
+    let mut writer = BufWriter::new(file);
+    for line in std::iter::once(String::from(header)).chain(rows) {
+        if let Err(err) = writeln!(writer, "{}", line) {
+            return Err(Error::Io(String::from(path), err));
+        }
+    }
+    match writer.flush() {
+        Ok(_) => Ok(()),
+        Err(err) => Err(Error::Io(String::from(path), err)),
+    }
+}
+
+// quote the field if it contains a separator, a quote or a line break.
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        String::from(value)
+    }
+}
+
+// the error column, empty without an error. the line breaks are replaced so that a row stays on
+// one line, the parser errors span several lines.
+pub fn csv_error(error: Option<&str>) -> String {
+    error
+        .map(|err| csv_field(&err.replace(['\n', '\r'], " ")))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{csv_error, csv_field, write_lines};
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("a.ply"), "a.ply");
+        assert_eq!(csv_field("a,b.ply"), "\"a,b.ply\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_csv_error() {
+        assert_eq!(csv_error(None), "");
+        assert_eq!(csv_error(Some("bad header")), "bad header");
+        assert_eq!(csv_error(Some("line 2,\nbad\r\n")), "\"line 2, bad  \"");
+    }
+
+    #[test]
+    fn test_write_lines() {
+        let path = std::env::temp_dir().join(format!("csv-{}.csv", std::process::id()));
+        let path = path.to_str().unwrap();
+        write_lines(path, "a,b", vec![String::from("1,2"), String::from("3,")]).unwrap();
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "a,b\n1,2\n3,\n");
+        std::fs::remove_file(path).unwrap();
+        assert!(write_lines("/nonexistent/a.csv", "a", vec![]).is_err());
+    }
+}
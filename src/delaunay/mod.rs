@@ -0,0 +1,8 @@
+mod tetrahedralization;
+mod triangulation;
+mod voronoi;
+
+pub(crate) use tetrahedralization::FACES;
+pub use tetrahedralization::{delaunay_3d, Tetrahedralization};
+pub use triangulation::{delaunay_2d, Triangulation};
+pub use voronoi::Voronoi;
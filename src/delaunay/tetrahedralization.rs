@@ -0,0 +1,548 @@
+use crate::error::{Error, Result};
+use crate::geometry::{Point, Triangle};
+use crate::predicates::{collinear3d, insphere, orient3d};
+use std::collections::HashMap;
+
+// the vertex at infinity, every hull face forms a ghost tetrahedron with it
+const INFINITE: usize = usize::MAX;
+const NONE: usize = usize::MAX;
+
+// the faces of a tetrahedron, face i is opposite to vertex i and wound so that vertex i is below
+// it: orient3d(face, vertex i) > 0 for a positively oriented tetrahedron.
+pub(crate) const FACES: [[usize; 3]; 4] = [[1, 3, 2], [0, 2, 3], [0, 3, 1], [0, 1, 2]];
+
+#[derive(Copy, Clone)]
+struct Tet {
+    vertices: [usize; 4],
+    // neighbors[i] is across the face opposite to vertices[i]
+    neighbors: [usize; 4],
+    alive: bool,
+}
+
+impl Tet {
+    fn is_ghost(&self) -> bool {
+        self.vertices.contains(&INFINITE)
+    }
+
+    fn index_of(&self, vertex: usize) -> usize {
+        self.vertices.iter().position(|v| *v == vertex).unwrap()
+    }
+}
+
+// incremental Bowyer-Watson tetrahedralization.
+// the hull faces are closed by ghost tetrahedra with the vertex at infinity, a point outside of
+// the hull conflicts with the ghosts of the faces it sees, so the result covers the exact convex
+// hull of the points. every finite tetrahedron is positively oriented, orient3d(a, b, c, d) > 0,
+// a ghost is positively oriented when its infinite vertex is replaced by a point above its face.
+struct Builder<'a> {
+    points: &'a [Point],
+    tets: Vec<Tet>,
+    free: Vec<usize>,
+    // a tetrahedron to start the next walk from
+    last: usize,
+}
+
+// the Delaunay tetrahedra of points, as indices into points.
+#[derive(Clone, Debug, Default)]
+pub struct Tetrahedralization {
+    // every tetrahedron is positively oriented, orient3d(a, b, c, d) > 0
+    pub tetrahedra: Vec<[usize; 4]>,
+    // neighbors[t][i] is across the face opposite to vertex i of tetrahedron t, None on the hull
+    pub neighbors: Vec<[Option<usize>; 4]>,
+}
+
+impl Tetrahedralization {
+    pub fn len(&self) -> usize {
+        self.tetrahedra.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tetrahedra.is_empty()
+    }
+
+    // the faces without a neighbor, the boundary of the tetrahedralization is the convex hull of
+    // the points. the faces are wound outward (orient3d(face, inside) > 0). the points on the
+    // hull that are not extreme points, e.g. in the middle of a flat side, are vertices too.
+    pub fn hull_faces(&self) -> Vec<[usize; 3]> {
+        let mut faces = vec![];
+        for (tet, neighbors) in self.tetrahedra.iter().zip(&self.neighbors) {
+            for (i, face) in FACES.iter().enumerate() {
+                if neighbors[i].is_none() {
+                    faces.push(face.map(|j| tet[j]));
+                }
+            }
+        }
+        faces
+    }
+
+    // the hull faces as triangles of points, the points the tetrahedralization was built from.
+    pub fn hull(&self, points: &[Point]) -> Vec<Triangle> {
+        self.hull_faces()
+            .iter()
+            .map(|face| Triangle::new(points[face[0]], points[face[1]], points[face[2]]))
+            .collect()
+    }
+}
+
+// the tetrahedralization of the points, with the vertex at infinity closing the hull.
+// duplicate points are only used once (the first one).
+pub fn delaunay_3d(points: &[Point]) -> Result<Tetrahedralization> {
+    let mut order = unique_points(points);
+    if order.len() < 4 {
+        return Err(Error::NotEnoughPoints {
+            required: 4,
+            found: order.len(),
+        });
+    }
+    let first = match first_tetrahedron(points, &order) {
+        Some(val) => val,
+        None => {
+            return Err(Error::Degenerate(String::from(
+                "the points are coplanar, there is no tetrahedron",
+            )))
+        }
+    };
+    order.retain(|i| !first.contains(i));
+    let mut builder = Builder::new(points, first);
+    for i in order {
+        builder.insert(i);
+    }
+    Ok(builder.finish())
+}
+
+// the indices of the points without the duplicates, in z-order so that consecutive points are
+// close and the walks are short.
+fn unique_points(points: &[Point]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..points.len()).collect();
+    order.sort_by(|a, b| {
+        let (a, b) = (&points[*a], &points[*b]);
+        a.x.total_cmp(&b.x)
+            .then(a.y.total_cmp(&b.y))
+            .then(a.z.total_cmp(&b.z))
+    });
+    order.dedup_by(|a, b| {
+        let (a, b) = (&points[*a], &points[*b]);
+        a.x == b.x && a.y == b.y && a.z == b.z
+    });
+    if order.is_empty() {
+        return order;
+    }
+    let mut min = (f64::MAX, f64::MAX, f64::MAX);
+    let mut max = (f64::MIN, f64::MIN, f64::MIN);
+    for i in &order {
+        let pt = &points[*i];
+        min = (min.0.min(pt.x), min.1.min(pt.y), min.2.min(pt.z));
+        max = (max.0.max(pt.x), max.1.max(pt.y), max.2.max(pt.z));
+    }
+    // 10 bits per axis
+    let cell = |val: f64, min: f64, max: f64| {
+        if max > min {
+            ((val - min) / (max - min) * 1023.0) as u64
+        } else {
+            0
+        }
+    };
+    let key = |i: &usize| {
+        let pt = &points[*i];
+        morton(
+            cell(pt.x, min.0, max.0),
+            cell(pt.y, min.1, max.1),
+            cell(pt.z, min.2, max.2),
+        )
+    };
+    order.sort_by_cached_key(key);
+    order
+}
+
+// interleave the bits of x, y, z
+fn morton(x: u64, y: u64, z: u64) -> u64 {
+    let mut key = 0;
+    for bit in 0..10 {
+        key |= ((x >> bit) & 1) << (3 * bit);
+        key |= ((y >> bit) & 1) << (3 * bit + 1);
+        key |= ((z >> bit) & 1) << (3 * bit + 2);
+    }
+    key
+}
+
+// 4 points that are not coplanar, positively oriented.
+fn first_tetrahedron(points: &[Point], order: &[usize]) -> Option<[usize; 4]> {
+    let a = order[0];
+    let b = order[1];
+    let c = *order[2..]
+        .iter()
+        .find(|i| !collinear3d(&points[a], &points[b], &points[**i]))?;
+    let d = *order[2..]
+        .iter()
+        .find(|i| orient3d(&points[a], &points[b], &points[c], &points[**i]) != 0.0)?;
+    if orient3d(&points[a], &points[b], &points[c], &points[d]) > 0.0 {
+        Some([a, b, c, d])
+    } else {
+        Some([b, a, c, d])
+    }
+}
+
+impl<'a> Builder<'a> {
+    fn new(points: &'a [Point], first: [usize; 4]) -> Self {
+        let mut builder = Builder {
+            points,
+            tets: vec![],
+            free: vec![],
+            last: 0,
+        };
+        let inner = builder.add(first);
+        // one ghost per face, with the face reversed so that it is positively oriented
+        for (i, face) in FACES.iter().enumerate() {
+            let ghost = builder.add([first[face[0]], first[face[2]], first[face[1]], INFINITE]);
+            builder.tets[inner].neighbors[i] = ghost;
+            builder.tets[ghost].neighbors[3] = inner;
+        }
+        // link the ghosts, they share the edges of the hull
+        builder.link(&(1..5).collect::<Vec<usize>>());
+        builder.last = inner;
+        builder
+    }
+
+    fn add(&mut self, vertices: [usize; 4]) -> usize {
+        let tet = Tet {
+            vertices,
+            neighbors: [NONE; 4],
+            alive: true,
+        };
+        match self.free.pop() {
+            Some(i) => {
+                self.tets[i] = tet;
+                i
+            }
+            None => {
+                self.tets.push(tet);
+                self.tets.len() - 1
+            }
+        }
+    }
+
+    // link the faces that the tetrahedra share, among the given ones
+    fn link(&mut self, tets: &[usize]) {
+        let mut faces: HashMap<[usize; 3], (usize, usize)> = HashMap::new();
+        for t in tets {
+            for i in 0..4 {
+                if self.tets[*t].neighbors[i] != NONE {
+                    continue;
+                }
+                let mut face = [0; 3];
+                let mut k = 0;
+                for (j, v) in self.tets[*t].vertices.iter().enumerate() {
+                    if j != i {
+                        face[k] = *v;
+                        k += 1;
+                    }
+                }
+                face.sort_unstable();
+                if let Some((other, j)) = faces.remove(&face) {
+                    self.tets[*t].neighbors[i] = other;
+                    self.tets[other].neighbors[j] = *t;
+                } else {
+                    faces.insert(face, (*t, i));
+                }
+            }
+        }
+    }
+
+    fn point(&self, vertex: usize) -> &Point {
+        &self.points[vertex]
+    }
+
+    // orientation of the tetrahedron with vertex i replaced by pt, positive if pt is on the same
+    // side of face i as vertex i. for a ghost with i the infinite vertex, positive if pt is above
+    // the hull face.
+    fn orient_with(&self, t: usize, i: usize, pt: &Point) -> f64 {
+        let v = &self.tets[t].vertices;
+        let p = |j: usize| if j == i { pt } else { self.point(v[j]) };
+        orient3d(p(0), p(1), p(2), p(3))
+    }
+
+    // whether pt is strictly inside the circumsphere of the tetrahedron. for a ghost: pt is above
+    // the hull face, or on its plane and inside its circumcircle.
+    fn conflicts(&self, t: usize, pt: &Point) -> bool {
+        let tet = &self.tets[t];
+        if tet.is_ghost() {
+            let inf = tet.index_of(INFINITE);
+            let side = self.orient_with(t, inf, pt);
+            if side != 0.0 {
+                return side > 0.0;
+            }
+            // the circumsphere of the inner neighbor cuts the plane along the circumcircle
+            return self.conflicts(tet.neighbors[inf], pt);
+        }
+        let v = &tet.vertices;
+        insphere(
+            self.point(v[0]),
+            self.point(v[1]),
+            self.point(v[2]),
+            self.point(v[3]),
+            pt,
+        ) > 0.0
+    }
+
+    // a tetrahedron in conflict with pt: the one that contains pt, or a ghost pt is above
+    fn locate(&self, pt: &Point) -> usize {
+        let mut t = self.last;
+        if self.tets[t].is_ghost() {
+            t = self.tets[t].neighbors[self.tets[t].index_of(INFINITE)];
+        }
+        // the visibility walk terminates in a Delaunay tetrahedralization, the limit is a guard
+        let mut steps = 0;
+        'walk: while steps <= self.tets.len() {
+            steps += 1;
+            if self.tets[t].is_ghost() {
+                break;
+            }
+            // start with a different face each step, so that the walk does not go around in
+            // circles on degenerate input
+            for k in 0..4 {
+                let i = (k + steps) % 4;
+                if self.orient_with(t, i, pt) < 0.0 {
+                    t = self.tets[t].neighbors[i];
+                    continue 'walk;
+                }
+            }
+            break;
+        }
+        if self.conflicts(t, pt) {
+            return t;
+        }
+        (0..self.tets.len())
+            .find(|t| self.tets[*t].alive && self.conflicts(*t, pt))
+            .unwrap()
+    }
+
+    fn insert(&mut self, vertex: usize) {
+        let pt = self.points[vertex];
+        let start = self.locate(&pt);
+
+        // the cavity: the tetrahedra in conflict, connected to the start
+        let mut cavity = vec![start];
+        self.tets[start].alive = false;
+        let mut boundary = vec![];
+        let mut next = 0;
+        while next < cavity.len() {
+            let t = cavity[next];
+            next += 1;
+            for i in 0..4 {
+                let n = self.tets[t].neighbors[i];
+                if !self.tets[n].alive {
+                    continue;
+                }
+                if self.conflicts(n, &pt) {
+                    self.tets[n].alive = false;
+                    cavity.push(n);
+                } else {
+                    boundary.push((t, i, n));
+                }
+            }
+        }
+
+        // a new tetrahedron for each face of the cavity, pt in place of the inner vertex
+        // the cavity is only freed afterwards, so that its slots are not reused while the
+        // outside neighbors still refer to them
+        let mut created = Vec::with_capacity(boundary.len());
+        for (t, i, n) in boundary {
+            let mut vertices = self.tets[t].vertices;
+            vertices[i] = vertex;
+            let new = self.add(vertices);
+            self.tets[new].neighbors[i] = n;
+            let j = self.tets[n].neighbors.iter().position(|x| *x == t).unwrap();
+            self.tets[n].neighbors[j] = new;
+            created.push(new);
+        }
+        self.link(&created);
+        self.free.extend(cavity);
+        self.last = *created.last().unwrap();
+    }
+
+    // the finite tetrahedra and their neighbors.
+    fn finish(self) -> Tetrahedralization {
+        let mut index = vec![NONE; self.tets.len()];
+        let mut tetrahedra = vec![];
+        for (t, tet) in self.tets.iter().enumerate() {
+            if tet.alive && !tet.is_ghost() {
+                index[t] = tetrahedra.len();
+                tetrahedra.push(tet.vertices);
+            }
+        }
+        let neighbors = self
+            .tets
+            .iter()
+            .filter(|tet| tet.alive && !tet.is_ghost())
+            .map(|tet| {
+                tet.neighbors.map(|n| match index[n] {
+                    NONE => None,
+                    val => Some(val),
+                })
+            })
+            .collect();
+        Tetrahedralization {
+            tetrahedra,
+            neighbors,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{delaunay_3d, Tetrahedralization};
+    use crate::convex_hull_3d::{self, convex_hull_surface_area, convex_hull_volume, Algorithm};
+    use crate::geometry::{Point, Triangle};
+    use crate::predicates::{insphere, orient3d};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn random_points(count: usize, seed: u64) -> Vec<Point> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..count)
+            .map(|_| {
+                Point::new(
+                    rng.gen_range(0.0..1.0),
+                    rng.gen_range(0.0..1.0),
+                    rng.gen_range(0.0..1.0),
+                )
+            })
+            .collect()
+    }
+
+    fn volume(points: &[Point], tet: &[usize; 4]) -> f64 {
+        let p = |i: usize| &points[tet[i]];
+        orient3d(p(0), p(1), p(2), p(3)) / 6.0
+    }
+
+    // every tetrahedron is positive, no point is inside a circumsphere, and the neighbors agree
+    fn check(points: &[Point], result: &Tetrahedralization) {
+        let (tetrahedra, neighbors) = (&result.tetrahedra, &result.neighbors);
+        for (t, tet) in tetrahedra.iter().enumerate() {
+            assert!(volume(points, tet) > 0.0);
+            for pt in points {
+                let p = |i: usize| &points[tet[i]];
+                assert!(insphere(p(0), p(1), p(2), p(3), pt) <= 0.0);
+            }
+            for (i, n) in neighbors[t].iter().enumerate() {
+                if let Some(n) = n {
+                    assert!(neighbors[*n].contains(&Some(t)));
+                    assert!(!tetrahedra[*n].contains(&tet[i]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_delaunay_3d_random() {
+        let points = random_points(300, 1);
+        let result = delaunay_3d(&points).unwrap();
+        check(&points, &result);
+        let tetrahedra = result.tetrahedra;
+        // the points are in general position, so every point is a vertex
+        let mut used = vec![false; points.len()];
+        for tet in &tetrahedra {
+            for v in tet {
+                used[*v] = true;
+            }
+        }
+        assert!(used.iter().all(|u| *u));
+    }
+
+    #[test]
+    fn test_delaunay_3d_grid() {
+        // cospherical and coplanar everywhere, the unit cubes are split into tetrahedra
+        let mut points = vec![];
+        for i in 0..4 {
+            for j in 0..4 {
+                for k in 0..4 {
+                    points.push(Point::new(i as f64, j as f64, k as f64));
+                }
+            }
+        }
+        // and every point twice
+        points.extend(points.clone());
+        let result = delaunay_3d(&points).unwrap();
+        check(&points, &result);
+        let tetrahedra = result.tetrahedra;
+        let total: f64 = tetrahedra.iter().map(|tet| volume(&points, tet)).sum();
+        assert!((total - 27.0).abs() < 0.000001, "{}", total);
+        assert!(tetrahedra.iter().flatten().all(|v| *v < 64));
+    }
+
+    // the vertices of a triangle in a canonical order, to compare hulls
+    fn sorted(tri: &Triangle) -> [(u64, u64, u64); 3] {
+        let key = |pt: &Point| (pt.x.to_bits(), pt.y.to_bits(), pt.z.to_bits());
+        let mut vertices = [key(&tri.p1), key(&tri.p2), key(&tri.p3)];
+        vertices.sort_unstable();
+        vertices
+    }
+
+    #[test]
+    fn test_delaunay_3d_hull_jarvis_march() {
+        // in general position the hull triangles are unique
+        let points = random_points(400, 5);
+        let result = delaunay_3d(&points).unwrap();
+        let hull = result.hull(&points);
+        let jarvis =
+            convex_hull_3d::convex_hull_with(points.clone(), Algorithm::JarvisMarch).unwrap();
+        let mut expected: Vec<_> = jarvis.iter().map(sorted).collect();
+        let mut found: Vec<_> = hull.iter().map(sorted).collect();
+        expected.sort_unstable();
+        found.sort_unstable();
+        assert_eq!(found, expected);
+
+        // the hull is closed, every edge of a face is in its neighbor the other way around
+        let faces = result.hull_faces();
+        for face in &faces {
+            for k in 0..3 {
+                let (a, b) = (face[k], face[(k + 1) % 3]);
+                assert!(faces
+                    .iter()
+                    .any(|other| (0..3).any(|l| other[l] == b && other[(l + 1) % 3] == a)));
+            }
+        }
+        // and wound outward: the points are below every face
+        for face in &faces {
+            let p = |i: usize| &points[face[i]];
+            assert!(points
+                .iter()
+                .all(|pt| orient3d(p(0), p(1), p(2), pt) >= 0.0));
+        }
+    }
+
+    #[test]
+    fn test_delaunay_3d_hull_coplanar_sides() {
+        // the sides of the box are triangulated differently, but cover the same surface
+        let mut points = random_points(100, 6);
+        for i in 0..=4 {
+            for j in 0..=4 {
+                let (u, v) = (i as f64 / 4.0, j as f64 / 4.0);
+                points.push(Point::new(u, v, 0.0));
+                points.push(Point::new(u, v, 1.0));
+                points.push(Point::new(u, 0.0, v));
+                points.push(Point::new(u, 1.0, v));
+                points.push(Point::new(0.0, u, v));
+                points.push(Point::new(1.0, u, v));
+            }
+        }
+        let hull = delaunay_3d(&points).unwrap().hull(&points);
+        let jarvis =
+            convex_hull_3d::convex_hull_with(points.clone(), Algorithm::JarvisMarch).unwrap();
+        assert!((convex_hull_volume(&hull) - 1.0).abs() < 0.000001);
+        assert!((convex_hull_volume(&jarvis) - 1.0).abs() < 0.000001);
+        assert!((convex_hull_surface_area(&hull) - 6.0).abs() < 0.000001);
+        assert!((convex_hull_surface_area(&jarvis) - 6.0).abs() < 0.000001);
+        // the points on the sides are vertices of the triangulation, 25 per side
+        assert_eq!(hull.len(), 6 * 32);
+    }
+
+    #[test]
+    fn test_delaunay_3d_degenerate() {
+        assert!(delaunay_3d(&random_points(3, 2)).is_err());
+        let flat: Vec<Point> = (0..10)
+            .map(|i| Point::new(i as f64, (i * i) as f64, 1.0))
+            .collect();
+        assert!(delaunay_3d(&flat).is_err());
+    }
+}
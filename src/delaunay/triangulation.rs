@@ -0,0 +1,408 @@
+use crate::convex_hull_2d::orientation;
+use crate::error::{Error, Result};
+use crate::geometry::Point;
+use crate::predicates::incircle;
+use std::collections::HashMap;
+
+// the vertex at infinity, every hull edge forms a ghost triangle with it
+const INFINITE: usize = usize::MAX;
+const NONE: usize = usize::MAX;
+
+// the Delaunay triangles of the points projected on the x-y plane, as indices into points.
+#[derive(Clone, Debug, Default)]
+pub struct Triangulation {
+    // every triangle is in counter-clockwise order, orientation(a, b, c) > 0
+    pub triangles: Vec<[usize; 3]>,
+    // neighbors[t][i] is across the edge opposite to vertex i of triangle t, None on the hull
+    pub neighbors: Vec<[Option<usize>; 3]>,
+}
+
+impl Triangulation {
+    pub fn len(&self) -> usize {
+        self.triangles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+    }
+
+    // the edges without a neighbor, in counter-clockwise order around the triangulation (the
+    // inside is on the left). the points on the hull that are not extreme points are included.
+    pub fn hull_edges(&self) -> Vec<[usize; 2]> {
+        let mut edges = vec![];
+        for (tri, neighbors) in self.triangles.iter().zip(&self.neighbors) {
+            for i in 0..3 {
+                if neighbors[i].is_none() {
+                    edges.push([tri[(i + 1) % 3], tri[(i + 2) % 3]]);
+                }
+            }
+        }
+        edges
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Tri {
+    vertices: [usize; 3],
+    // neighbors[i] is across the edge opposite to vertices[i]
+    neighbors: [usize; 3],
+    alive: bool,
+}
+
+impl Tri {
+    fn is_ghost(&self) -> bool {
+        self.vertices.contains(&INFINITE)
+    }
+
+    fn index_of(&self, vertex: usize) -> usize {
+        self.vertices.iter().position(|v| *v == vertex).unwrap()
+    }
+}
+
+// incremental Bowyer-Watson triangulation, the 2D version of the tetrahedralization: the hull
+// edges are closed by ghost triangles with the vertex at infinity.
+struct Builder<'a> {
+    points: &'a [Point],
+    tris: Vec<Tri>,
+    free: Vec<usize>,
+    // a triangle to start the next walk from
+    last: usize,
+}
+
+// the triangulation of the points in the x-y plane, z is ignored.
+// the points at the same x-y are only used once (the first one).
+pub fn delaunay_2d(points: &[Point]) -> Result<Triangulation> {
+    let mut order: Vec<usize> = (0..points.len()).collect();
+    order.sort_by(|a, b| {
+        let (a, b) = (&points[*a], &points[*b]);
+        a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y))
+    });
+    order.dedup_by(|a, b| points[*a].x == points[*b].x && points[*a].y == points[*b].y);
+    if order.len() < 3 {
+        return Err(Error::NotEnoughPoints {
+            required: 3,
+            found: order.len(),
+        });
+    }
+    let first = match first_triangle(points, &order) {
+        Some(val) => val,
+        None => {
+            return Err(Error::Degenerate(String::from(
+                "the points are collinear, there is no triangle",
+            )))
+        }
+    };
+    // insert along a snake through vertical strips, so that the walks are short
+    order.retain(|i| !first.contains(i));
+    sort_in_strips(points, &mut order);
+    let mut builder = Builder::new(points, first);
+    for i in order {
+        builder.insert(i);
+    }
+    Ok(builder.finish())
+}
+
+fn first_triangle(points: &[Point], order: &[usize]) -> Option<[usize; 3]> {
+    let (a, b) = (order[0], order[1]);
+    let c = *order[2..]
+        .iter()
+        .find(|i| orientation(&points[a], &points[b], &points[**i]) != 0)?;
+    if orientation(&points[a], &points[b], &points[c]) > 0 {
+        Some([a, b, c])
+    } else {
+        Some([b, a, c])
+    }
+}
+
+// vertical strips of about sqrt(n) points, bottom to top and top to bottom in turns.
+fn sort_in_strips(points: &[Point], order: &mut [usize]) {
+    let (mut min_x, mut max_x) = (f64::MAX, f64::MIN);
+    for i in order.iter() {
+        min_x = min_x.min(points[*i].x);
+        max_x = max_x.max(points[*i].x);
+    }
+    let strips = (order.len() as f64).sqrt().ceil().max(1.0);
+    let width = (max_x - min_x) / strips;
+    let strip = |i: &usize| {
+        if width > 0.0 {
+            (((points[*i].x - min_x) / width) as usize).min(strips as usize - 1)
+        } else {
+            0
+        }
+    };
+    order.sort_by(|a, b| {
+        let (sa, sb) = (strip(a), strip(b));
+        let (ya, yb) = (points[*a].y, points[*b].y);
+        sa.cmp(&sb).then(if sa % 2 == 0 {
+            ya.total_cmp(&yb)
+        } else {
+            yb.total_cmp(&ya)
+        })
+    });
+}
+
+impl<'a> Builder<'a> {
+    fn new(points: &'a [Point], first: [usize; 3]) -> Self {
+        let mut builder = Builder {
+            points,
+            tris: vec![],
+            free: vec![],
+            last: 0,
+        };
+        let inner = builder.add(first);
+        // one ghost per edge, with the edge reversed
+        for i in 0..3 {
+            let ghost = builder.add([first[(i + 2) % 3], first[(i + 1) % 3], INFINITE]);
+            builder.tris[inner].neighbors[i] = ghost;
+            builder.tris[ghost].neighbors[2] = inner;
+        }
+        builder.link(&[1, 2, 3]);
+        builder.last = inner;
+        builder
+    }
+
+    fn add(&mut self, vertices: [usize; 3]) -> usize {
+        let tri = Tri {
+            vertices,
+            neighbors: [NONE; 3],
+            alive: true,
+        };
+        match self.free.pop() {
+            Some(i) => {
+                self.tris[i] = tri;
+                i
+            }
+            None => {
+                self.tris.push(tri);
+                self.tris.len() - 1
+            }
+        }
+    }
+
+    // link the edges that the triangles share, among the given ones
+    fn link(&mut self, tris: &[usize]) {
+        let mut edges: HashMap<[usize; 2], (usize, usize)> = HashMap::new();
+        for t in tris {
+            for i in 0..3 {
+                if self.tris[*t].neighbors[i] != NONE {
+                    continue;
+                }
+                let v = &self.tris[*t].vertices;
+                let (a, b) = (v[(i + 1) % 3], v[(i + 2) % 3]);
+                let edge = [a.min(b), a.max(b)];
+                if let Some((other, j)) = edges.remove(&edge) {
+                    self.tris[*t].neighbors[i] = other;
+                    self.tris[other].neighbors[j] = *t;
+                } else {
+                    edges.insert(edge, (*t, i));
+                }
+            }
+        }
+    }
+
+    // orientation of the triangle with vertex i replaced by pt: positive if pt is on the same
+    // side of edge i as vertex i. for a ghost with i the infinite vertex, positive if pt is
+    // outside of the hull edge.
+    fn orient_with(&self, t: usize, i: usize, pt: &Point) -> i32 {
+        let v = &self.tris[t].vertices;
+        let p = |j: usize| if j == i { pt } else { &self.points[v[j]] };
+        orientation(p(0), p(1), p(2))
+    }
+
+    // whether pt is strictly inside the circumcircle of the triangle. for a ghost: pt is outside
+    // of the hull edge, or on its line and strictly between its end points.
+    fn conflicts(&self, t: usize, pt: &Point) -> bool {
+        let tri = &self.tris[t];
+        if tri.is_ghost() {
+            let inf = tri.index_of(INFINITE);
+            let side = self.orient_with(t, inf, pt);
+            if side != 0 {
+                return side > 0;
+            }
+            // the circumcircle of the inner neighbor cuts the line at the end points
+            return self.conflicts(tri.neighbors[inf], pt);
+        }
+        let p = |j: usize| &self.points[tri.vertices[j]];
+        incircle(p(0), p(1), p(2), pt) > 0.0
+    }
+
+    fn locate(&self, pt: &Point) -> usize {
+        let mut t = self.last;
+        if self.tris[t].is_ghost() {
+            t = self.tris[t].neighbors[self.tris[t].index_of(INFINITE)];
+        }
+        let mut steps = 0;
+        'walk: while steps <= self.tris.len() {
+            steps += 1;
+            if self.tris[t].is_ghost() {
+                break;
+            }
+            for k in 0..3 {
+                let i = (k + steps) % 3;
+                if self.orient_with(t, i, pt) < 0 {
+                    t = self.tris[t].neighbors[i];
+                    continue 'walk;
+                }
+            }
+            break;
+        }
+        if self.conflicts(t, pt) {
+            return t;
+        }
+        (0..self.tris.len())
+            .find(|t| self.tris[*t].alive && self.conflicts(*t, pt))
+            .unwrap()
+    }
+
+    fn insert(&mut self, vertex: usize) {
+        let pt = self.points[vertex];
+        let start = self.locate(&pt);
+
+        let mut cavity = vec![start];
+        self.tris[start].alive = false;
+        let mut boundary = vec![];
+        let mut next = 0;
+        while next < cavity.len() {
+            let t = cavity[next];
+            next += 1;
+            for i in 0..3 {
+                let n = self.tris[t].neighbors[i];
+                if !self.tris[n].alive {
+                    continue;
+                }
+                if self.conflicts(n, &pt) {
+                    self.tris[n].alive = false;
+                    cavity.push(n);
+                } else {
+                    boundary.push((t, i, n));
+                }
+            }
+        }
+
+        // a new triangle for each edge of the cavity, the cavity is freed afterwards
+        let mut created = Vec::with_capacity(boundary.len());
+        for (t, i, n) in boundary {
+            let mut vertices = self.tris[t].vertices;
+            vertices[i] = vertex;
+            let new = self.add(vertices);
+            self.tris[new].neighbors[i] = n;
+            let j = self.tris[n].neighbors.iter().position(|x| *x == t).unwrap();
+            self.tris[n].neighbors[j] = new;
+            created.push(new);
+        }
+        self.link(&created);
+        self.free.extend(cavity);
+        self.last = *created.last().unwrap();
+    }
+
+    fn finish(self) -> Triangulation {
+        let mut index = vec![NONE; self.tris.len()];
+        let mut triangles = vec![];
+        for (t, tri) in self.tris.iter().enumerate() {
+            if tri.alive && !tri.is_ghost() {
+                index[t] = triangles.len();
+                triangles.push(tri.vertices);
+            }
+        }
+        let neighbors = self
+            .tris
+            .iter()
+            .filter(|tri| tri.alive && !tri.is_ghost())
+            .map(|tri| {
+                tri.neighbors.map(|n| match index[n] {
+                    NONE => None,
+                    val => Some(val),
+                })
+            })
+            .collect();
+        Triangulation {
+            triangles,
+            neighbors,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{delaunay_2d, Triangulation};
+    use crate::convex_hull_2d::{convex_hull, convex_hull_area};
+    use crate::geometry::Point;
+    use crate::predicates::{incircle, orient2d};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn random_points(count: usize, seed: u64) -> Vec<Point> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..count)
+            .map(|_| {
+                Point::new(
+                    rng.gen_range(0.0..1.0),
+                    rng.gen_range(0.0..1.0),
+                    rng.gen_range(0.0..0.2),
+                )
+            })
+            .collect()
+    }
+
+    fn area(points: &[Point], tri: &[usize; 3]) -> f64 {
+        orient2d(&points[tri[0]], &points[tri[1]], &points[tri[2]]) / 2.0
+    }
+
+    // every triangle is counter-clockwise, no point is inside a circumcircle, and the neighbors
+    // agree
+    fn check(points: &[Point], result: &Triangulation) {
+        for (t, tri) in result.triangles.iter().enumerate() {
+            assert!(area(points, tri) > 0.0);
+            let p = |i: usize| &points[tri[i]];
+            for pt in points {
+                assert!(incircle(p(0), p(1), p(2), pt) <= 0.0);
+            }
+            for (i, n) in result.neighbors[t].iter().enumerate() {
+                if let Some(n) = n {
+                    assert!(result.neighbors[*n].contains(&Some(t)));
+                    assert!(!result.triangles[*n].contains(&tri[i]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_delaunay_2d_random() {
+        let points = random_points(500, 1);
+        let result = delaunay_2d(&points).unwrap();
+        check(&points, &result);
+        // the triangles cover the convex hull, 2n - 2 - h triangles
+        let hull = convex_hull(points.clone()).unwrap();
+        let total: f64 = result.triangles.iter().map(|tri| area(&points, tri)).sum();
+        assert!((total - convex_hull_area(&hull)).abs() < 0.000001);
+        assert_eq!(result.len(), 2 * points.len() - 2 - hull.len());
+        assert_eq!(result.hull_edges().len(), hull.len());
+    }
+
+    #[test]
+    fn test_delaunay_2d_grid() {
+        // cocircular everywhere, with collinear points on the hull and duplicates in x-y
+        let mut points = vec![];
+        for i in 0..10 {
+            for j in 0..10 {
+                points.push(Point::new(i as f64, j as f64, 0.0));
+                points.push(Point::new(i as f64, j as f64, 1.0));
+            }
+        }
+        let result = delaunay_2d(&points).unwrap();
+        check(&points, &result);
+        assert_eq!(result.len(), 2 * 81);
+        assert_eq!(result.hull_edges().len(), 36);
+        // the first of the duplicates
+        assert!(result.triangles.iter().flatten().all(|v| v % 2 == 0));
+    }
+
+    #[test]
+    fn test_delaunay_2d_degenerate() {
+        assert!(delaunay_2d(&random_points(2, 2)).is_err());
+        let line: Vec<Point> = (0..10)
+            .map(|i| Point::new(i as f64, 2.0 * i as f64, 1.0))
+            .collect();
+        assert!(delaunay_2d(&line).is_err());
+    }
+}
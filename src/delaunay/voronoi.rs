@@ -0,0 +1,141 @@
+use super::triangulation::Triangulation;
+use crate::convex_hull_2d::convex_hull_area;
+use crate::geometry::Point;
+
+// Voronoi diagram of the points in the x-y plane, the dual of the Delaunay triangulation.
+// the vertices are the circumcenters of the triangles, and the cell of a point is the polygon of
+// the circumcenters of the triangles around it.
+#[derive(Clone, Debug, Default)]
+pub struct Voronoi {
+    // one per triangle, z is the mean z of the triangle
+    pub vertices: Vec<Point>,
+    // the cell of each point, in counter-clockwise order. None for the points on the hull, whose
+    // cells are unbounded, and for the points that are not in the triangulation (duplicates).
+    pub cells: Vec<Option<Vec<usize>>>,
+}
+
+impl Voronoi {
+    // points is what the triangulation was built from
+    pub fn new(triangulation: &Triangulation, points: &[Point]) -> Self {
+        let vertices = triangulation
+            .triangles
+            .iter()
+            .map(|tri| circumcenter(&points[tri[0]], &points[tri[1]], &points[tri[2]]))
+            .collect();
+        // a triangle around each point
+        let mut incident = vec![None; points.len()];
+        for (t, tri) in triangulation.triangles.iter().enumerate() {
+            for v in tri {
+                incident[*v] = Some(t);
+            }
+        }
+        let cells = incident
+            .iter()
+            .enumerate()
+            .map(|(v, start)| start.and_then(|start| cell(triangulation, v, start)))
+            .collect();
+        Voronoi { vertices, cells }
+    }
+
+    pub fn cell(&self, i: usize) -> Option<Vec<Point>> {
+        self.cells[i]
+            .as_ref()
+            .map(|cell| cell.iter().map(|v| self.vertices[*v]).collect())
+    }
+
+    // area of the cell in the x-y plane, the space around the point
+    pub fn cell_area(&self, i: usize) -> Option<f64> {
+        self.cell(i).map(|cell| convex_hull_area(&cell))
+    }
+
+    // the bounded cells as polygons of vertex indices, for a PLY face element
+    pub fn polygons(&self) -> Vec<Vec<u32>> {
+        self.cells
+            .iter()
+            .flatten()
+            .map(|cell| cell.iter().map(|v| *v as u32).collect())
+            .collect()
+    }
+}
+
+// the triangles around vertex v, in counter-clockwise order, None if v is on the hull
+fn cell(triangulation: &Triangulation, v: usize, start: usize) -> Option<Vec<usize>> {
+    let mut result = vec![];
+    let mut t = start;
+    loop {
+        result.push(t);
+        let tri = &triangulation.triangles[t];
+        let i = tri.iter().position(|x| *x == v).unwrap();
+        // the next triangle counter-clockwise is across the edge from v to the previous vertex
+        t = triangulation.neighbors[t][(i + 1) % 3]?;
+        if t == start {
+            return Some(result);
+        }
+    }
+}
+
+fn circumcenter(a: &Point, b: &Point, c: &Point) -> Point {
+    let (bx, by) = (b.x - a.x, b.y - a.y);
+    let (cx, cy) = (c.x - a.x, c.y - a.y);
+    let d = 2.0 * (bx * cy - by * cx);
+    let (lb, lc) = (bx * bx + by * by, cx * cx + cy * cy);
+    Point::new(
+        a.x + (cy * lb - by * lc) / d,
+        a.y + (bx * lc - cx * lb) / d,
+        (a.z + b.z + c.z) / 3.0,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Voronoi;
+    use crate::delaunay::delaunay_2d;
+    use crate::geometry::Point;
+
+    #[test]
+    fn test_voronoi_grid() {
+        // the cells of the inner points of a unit grid are unit squares around them
+        let mut points = vec![];
+        for i in 0..6 {
+            for j in 0..6 {
+                points.push(Point::new(i as f64, j as f64, 2.0));
+            }
+        }
+        let voronoi = Voronoi::new(&delaunay_2d(&points).unwrap(), &points);
+        for (i, pt) in points.iter().enumerate() {
+            let inner = pt.x > 0.0 && pt.x < 5.0 && pt.y > 0.0 && pt.y < 5.0;
+            assert_eq!(voronoi.cells[i].is_some(), inner);
+            if inner {
+                assert!((voronoi.cell_area(i).unwrap() - 1.0).abs() < 0.000001);
+                for corner in voronoi.cell(i).unwrap() {
+                    assert!((corner.x - pt.x).abs() == 0.5 && (corner.y - pt.y).abs() == 0.5);
+                    assert_eq!(corner.z, 2.0);
+                }
+            }
+        }
+        assert_eq!(voronoi.polygons().len(), 16);
+    }
+
+    #[test]
+    fn test_voronoi_cells_are_ccw() {
+        // a hexagon around the origin, the cell of the origin is the hexagon of the circumcenters
+        let mut points = vec![Point::new2d(0.0, 0.0)];
+        for k in 0..6 {
+            let angle = k as f64 * std::f64::consts::PI / 3.0;
+            points.push(Point::new2d(angle.cos(), angle.sin()));
+        }
+        let voronoi = Voronoi::new(&delaunay_2d(&points).unwrap(), &points);
+        let cell = voronoi.cell(0).unwrap();
+        assert_eq!(cell.len(), 6);
+        let mut signed = 0.0;
+        for k in 0..cell.len() {
+            let (a, b) = (&cell[k], &cell[(k + 1) % cell.len()]);
+            signed += a.x * b.y - a.y * b.x;
+        }
+        assert!(signed > 0.0);
+        // a regular hexagon of inradius 0.5
+        let area = 6.0 * 0.5 * 0.5 / 3.0f64.sqrt();
+        assert!((voronoi.cell_area(0).unwrap() - area).abs() < 0.000001);
+        assert!(voronoi.cells[1..].iter().all(|cell| cell.is_none()));
+    }
+}
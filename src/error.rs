@@ -0,0 +1,49 @@
+use std::fmt::{Display, Formatter};
+
+// errors of reading and writing point clouds and computing the hulls.
+#[derive(Debug)]
+pub enum Error {
+    // the file cannot be opened, read or written
+    Io(String, std::io::Error),
+    // the file is not a valid PLY file
+    Parse(String, std::io::Error),
+    // the PLY file has no element with this name
+    MissingElement(String),
+    // a vertex property is missing, is not a scalar or is not a finite number
+    InvalidProperty(String),
+    // an argument of an algorithm is out of range
+    InvalidArgument(String),
+    // the algorithm needs more points than it is given
+    NotEnoughPoints { required: usize, found: usize },
+    // the hull cannot be built on the points, e.g. all points are collinear
+    Degenerate(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(path, err) => write!(f, "{}: {}", path, err),
+            Error::Parse(path, err) => write!(f, "{}: invalid ply file, {}", path, err),
+            Error::MissingElement(name) => write!(f, "ply file has no {} element", name),
+            Error::InvalidProperty(msg) => write!(f, "invalid property, {}", msg),
+            Error::InvalidArgument(msg) => write!(f, "invalid argument, {}", msg),
+            Error::NotEnoughPoints { required, found } => write!(
+                f,
+                "not enough points, {} required but {} found",
+                required, found
+            ),
+            Error::Degenerate(msg) => write!(f, "degenerate input, {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(_, err) | Error::Parse(_, err) => Some(err),
+            _ => None,
+        }
+    }
+}
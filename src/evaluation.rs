@@ -0,0 +1,404 @@
+use crate::csv_file::{self, csv_error, csv_field};
+use crate::error::{Error, Result};
+use crate::geometry::Point;
+use crate::ply_file::{self, PlyPoint};
+use crate::soil_removal::{self, ColorConfig, SoilRemovalConfig, SoilRemovalMode};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// name of the per-vertex property with the hand label.
+pub const LABEL_PROPERTY: &str = "label";
+// label of the soil points, any other label is plant.
+pub const DEFAULT_SOIL_LABEL: f64 = 0.0;
+
+// a soil removal method to evaluate.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Method {
+    Soil(SoilRemovalConfig),
+    Color(ColorConfig),
+}
+
+impl Method {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Method::Soil(config) => match config.mode {
+                SoilRemovalMode::Buckets { .. } => "buckets",
+                SoilRemovalMode::Percentile(_) => "percentile",
+                SoilRemovalMode::Height(_) => "height",
+                SoilRemovalMode::Otsu => "otsu",
+                SoilRemovalMode::Ransac(_) => "ransac",
+                SoilRemovalMode::Band(_) => "band",
+            },
+            Method::Color(_) => "color",
+        }
+    }
+
+    // the plant points found by the method.
+    pub fn run(&self, points: Vec<PlyPoint>) -> Result<Vec<Point>> {
+        match self {
+            Method::Soil(config) => {
+                let mut config = *config;
+                // leveling moves the points, and does not change which of them are soil
+                if let SoilRemovalMode::Ransac(ransac) = &mut config.mode {
+                    ransac.level = false;
+                }
+                let points = points.into_iter().map(|pt| pt.point).collect();
+                soil_removal::remove_soil_with(points, &config)
+            }
+            Method::Color(config) => soil_removal::remove_soil_by_color(points, config),
+        }
+    }
+}
+
+// confusion counts of a segmentation, plant is the positive class.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Metrics {
+    pub true_positive: usize,
+    pub false_positive: usize,
+    pub false_negative: usize,
+    pub true_negative: usize,
+}
+
+impl Metrics {
+    // compare the predicted plant of every point with its label.
+    pub fn from_labels(predicted: &[bool], is_plant: &[bool]) -> Metrics {
+        let mut metrics = Metrics::default();
+        for (predicted, label) in predicted.iter().zip(is_plant) {
+            match (predicted, label) {
+                (true, true) => metrics.true_positive += 1,
+                (true, false) => metrics.false_positive += 1,
+                (false, true) => metrics.false_negative += 1,
+                (false, false) => metrics.true_negative += 1,
+            }
+        }
+        metrics
+    }
+
+    // sum of the counts, for the metrics over several files.
+    pub fn add(&mut self, other: &Metrics) {
+        self.true_positive += other.true_positive;
+        self.false_positive += other.false_positive;
+        self.false_negative += other.false_negative;
+        self.true_negative += other.true_negative;
+    }
+
+    pub fn precision(&self) -> f64 {
+        ratio(self.true_positive, self.true_positive + self.false_positive)
+    }
+
+    pub fn recall(&self) -> f64 {
+        ratio(self.true_positive, self.true_positive + self.false_negative)
+    }
+
+    // intersection over union of the predicted and the labelled plant
+    pub fn iou(&self) -> f64 {
+        ratio(
+            self.true_positive,
+            self.true_positive + self.false_positive + self.false_negative,
+        )
+    }
+
+    pub fn f1(&self) -> f64 {
+        ratio(
+            2 * self.true_positive,
+            2 * self.true_positive + self.false_positive + self.false_negative,
+        )
+    }
+}
+
+// 0 when there is nothing to count, e.g. the precision when no point is predicted plant, so
+// that a method that finds nothing does not score well.
+fn ratio(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
+
+// the points of a labelled PLY, and whether each is plant.
+pub fn read_labelled(path: &str, soil_label: f64) -> Result<(Vec<PlyPoint>, Vec<bool>)> {
+    let ply = ply_file::read_ply_file(String::from(path))?;
+    let points = ply_file::ply_to_ply_points(ply)?;
+    let mut is_plant = Vec::with_capacity(points.len());
+    for pt in &points {
+        match pt.properties.get(LABEL_PROPERTY) {
+            Some(label) => is_plant.push(*label != soil_label),
+            None => {
+                return Err(Error::InvalidProperty(format!(
+                    "vertex {} has no {}",
+                    pt.point, LABEL_PROPERTY
+                )))
+            }
+        }
+    }
+    Ok((points, is_plant))
+}
+
+// run the method on the points and compare its plant with the labels.
+pub fn evaluate(points: Vec<PlyPoint>, is_plant: &[bool], method: &Method) -> Result<Metrics> {
+    let coords: Vec<Point> = points.iter().map(|pt| pt.point).collect();
+    let plant = method.run(points)?;
+    Ok(Metrics::from_labels(&predicted(&coords, &plant), is_plant))
+}
+
+// the methods return the plant points sorted, they are matched back to the input by coordinates.
+// points with the same coordinates are counted as many times as they are in the plant.
+fn predicted(points: &[Point], plant: &[Point]) -> Vec<bool> {
+    let key = |pt: &Point| (pt.x.to_bits(), pt.y.to_bits(), pt.z.to_bits());
+    let mut remaining: HashMap<(u64, u64, u64), usize> = HashMap::new();
+    for pt in plant {
+        *remaining.entry(key(pt)).or_insert(0) += 1;
+    }
+    points
+        .iter()
+        .map(|pt| match remaining.get_mut(&key(pt)) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            }
+            _ => false,
+        })
+        .collect()
+}
+
+// metrics of one method on one file, or the error that stopped it.
+#[derive(Clone, Debug, Serialize)]
+pub struct EvaluationRow {
+    pub path: String,
+    pub method: String,
+    // position of the method in the evaluated methods, several methods can have the same name
+    pub method_index: usize,
+    pub metrics: Option<Metrics>,
+    pub error: Option<String>,
+}
+
+// evaluate every method on every file in parallel (on the current rayon pool). the rows are
+// ordered by file, then by method.
+pub fn evaluate_files(
+    paths: &[PathBuf],
+    methods: &[Method],
+    soil_label: f64,
+) -> Vec<EvaluationRow> {
+    paths
+        .par_iter()
+        .flat_map_iter(|path| evaluate_file(path, methods, soil_label))
+        .collect()
+}
+
+fn evaluate_file(path: &Path, methods: &[Method], soil_label: f64) -> Vec<EvaluationRow> {
+    let display = path.display().to_string();
+    // a file that cannot be read fails every method
+    let labelled = read_labelled(&display, soil_label).map_err(|err| err.to_string());
+    methods
+        .iter()
+        .enumerate()
+        .map(|(method_index, method)| {
+            let result = match &labelled {
+                Ok((points, is_plant)) => {
+                    evaluate(points.clone(), is_plant, method).map_err(|err| err.to_string())
+                }
+                Err(err) => Err(err.clone()),
+            };
+            let (metrics, error) = match result {
+                Ok(metrics) => (Some(metrics), None),
+                Err(err) => (None, Some(err)),
+            };
+            EvaluationRow {
+                path: display.clone(),
+                method: String::from(method.name()),
+                method_index,
+                metrics,
+                error,
+            }
+        })
+        .collect()
+}
+
+// the counts of every method summed over the files it did not fail on, in the order of the
+// methods. None for a method that failed on every file.
+pub fn summarize(rows: &[EvaluationRow], methods: &[Method]) -> Vec<(String, Option<Metrics>)> {
+    methods
+        .iter()
+        .enumerate()
+        .map(|(i, method)| {
+            let mut total = None;
+            for row in rows.iter().filter(|row| row.method_index == i) {
+                if let Some(metrics) = &row.metrics {
+                    total.get_or_insert_with(Metrics::default).add(metrics);
+                }
+            }
+            (String::from(method.name()), total)
+        })
+        .collect()
+}
+
+const CSV_HEADER: &str = "path,method,true_positive,false_positive,false_negative,true_negative,precision,recall,iou,f1,error";
+
+// one row per file and method, the metric columns are empty for a failed method.
+pub fn write_csv(rows: &[EvaluationRow], path: &str) -> Result<()> {
+    let lines = rows.iter().map(|row| {
+        let metrics = match &row.metrics {
+            Some(m) => format!(
+                "{},{},{},{},{},{},{},{}",
+                m.true_positive,
+                m.false_positive,
+                m.false_negative,
+                m.true_negative,
+                m.precision(),
+                m.recall(),
+                m.iou(),
+                m.f1()
+            ),
+            None => String::from(",,,,,,,"),
+        };
+        format!(
+            "{},{},{},{}",
+            csv_field(&row.path),
+            row.method,
+            metrics,
+            csv_error(row.error.as_deref())
+        )
+    });
+    csv_file::write_lines(path, CSV_HEADER, lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        evaluate_files, predicted, summarize, write_csv, Method, Metrics, DEFAULT_SOIL_LABEL,
+    };
+    use crate::geometry::Point;
+    use crate::soil_removal::{ColorConfig, SoilRemovalConfig, SoilRemovalMode};
+    use std::path::PathBuf;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("evaluation-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // 60 soil points (label 0) at z = 0 - 0.2 and 40 plant points (label 1) from z = 5, the
+    // points of the plant below z = 5.5 are labelled soil by mistake
+    fn labelled_ply() -> String {
+        let mut lines = vec![];
+        for i in 0..60 {
+            lines.push(format!("{} 0 {} 0", i, (i % 3) as f64 * 0.1));
+        }
+        for i in 0..40 {
+            let z = 5.0 + i as f64 * 0.1;
+            let label = if z < 5.5 { 0 } else { 1 };
+            lines.push(format!("{} 1 {} {}", i, z, label));
+        }
+        format!(
+            "ply\nformat ascii 1.0\nelement vertex {}\nproperty double x\nproperty double y\n\
+             property double z\nproperty uchar label\nend_header\n{}\n",
+            lines.len(),
+            lines.join("\n")
+        )
+    }
+
+    #[test]
+    fn test_metrics() {
+        let metrics = Metrics::from_labels(
+            &[true, true, true, false, false, false],
+            &[true, true, false, true, false, false],
+        );
+        assert_eq!(
+            metrics,
+            Metrics {
+                true_positive: 2,
+                false_positive: 1,
+                false_negative: 1,
+                true_negative: 2,
+            }
+        );
+        assert!((metrics.precision() - 2.0 / 3.0).abs() < 0.000001);
+        assert!((metrics.recall() - 2.0 / 3.0).abs() < 0.000001);
+        assert!((metrics.iou() - 0.5).abs() < 0.000001);
+        assert!((metrics.f1() - 2.0 / 3.0).abs() < 0.000001);
+        // nothing to find and nothing found
+        let empty = Metrics::from_labels(&[false], &[false]);
+        assert_eq!(empty.precision(), 0.0);
+        assert_eq!(empty.iou(), 0.0);
+    }
+
+    #[test]
+    fn test_metrics_all_soil() {
+        // no point is predicted plant, the precision is not perfect
+        let metrics =
+            Metrics::from_labels(&[false, false, false, false], &[true, true, false, false]);
+        assert_eq!(metrics.precision(), 0.0);
+        assert_eq!(metrics.recall(), 0.0);
+        assert_eq!(metrics.iou(), 0.0);
+        assert_eq!(metrics.f1(), 0.0);
+    }
+
+    #[test]
+    fn test_predicted_duplicates() {
+        let a = Point::new(1.0, 2.0, 3.0);
+        let b = Point::new(1.0, 2.0, 4.0);
+        assert_eq!(
+            predicted(&[a, b, a, a], &[a, a]),
+            vec![true, false, true, false]
+        );
+    }
+
+    #[test]
+    fn test_evaluate_files() {
+        let dir = test_dir("files");
+        std::fs::write(dir.join("a.ply"), labelled_ply()).unwrap();
+        let unlabelled = "ply\nformat ascii 1.0\nelement vertex 1\nproperty double x\n\
+                          property double y\nproperty double z\nend_header\n0 0 0\n";
+        std::fs::write(dir.join("b.ply"), unlabelled).unwrap();
+        let paths = crate::batch::find_ply_files(dir.to_str().unwrap()).unwrap();
+
+        let methods = [
+            Method::Soil(SoilRemovalConfig::default()),
+            Method::Soil(SoilRemovalConfig {
+                mode: SoilRemovalMode::Height(5.45),
+                bucket_count: 100,
+            }),
+            Method::Color(ColorConfig::default()),
+            // everything is soil
+            Method::Soil(SoilRemovalConfig {
+                mode: SoilRemovalMode::Height(100.0),
+                bucket_count: 100,
+            }),
+        ];
+        let rows = evaluate_files(&paths, &methods, DEFAULT_SOIL_LABEL);
+        assert_eq!(rows.len(), 8);
+        // the default cut keeps the whole plant, 5 of its points are labelled soil
+        let buckets = rows[0].metrics.unwrap();
+        assert_eq!(rows[0].method, "buckets");
+        assert_eq!(buckets.true_positive, 35);
+        assert_eq!(buckets.false_positive, 5);
+        assert_eq!(buckets.false_negative, 0);
+        assert_eq!(buckets.recall(), 1.0);
+        assert!((buckets.precision() - 35.0 / 40.0).abs() < 0.000001);
+        // the height cut matches the labels
+        assert_eq!(rows[1].metrics.unwrap().f1(), 1.0);
+        // no colors
+        assert!(rows[2].metrics.is_none());
+        // no labels, every method fails
+        assert!(rows[4..].iter().all(|row| row.error.is_some()));
+
+        let summary = summarize(&rows, &methods);
+        assert_eq!(summary.len(), 4);
+        assert_eq!(summary[0].1, Some(buckets));
+        assert_eq!(summary[2].1, None);
+        // the 2 height methods are not merged
+        assert_eq!(summary[1].0, summary[3].0);
+        assert_eq!(summary[1].1.unwrap().f1(), 1.0);
+        assert_eq!(summary[3].1.unwrap().true_positive, 0);
+
+        let csv = dir.join("evaluation.csv");
+        write_csv(&rows, csv.to_str().unwrap()).unwrap();
+        let content = std::fs::read_to_string(&csv).unwrap();
+        assert_eq!(content.lines().count(), 9);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
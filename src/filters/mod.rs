@@ -0,0 +1,8 @@
+mod outliers;
+mod voxel;
+
+pub use outliers::{
+    radius_outlier_removal, remove_outliers, statistical_outlier_removal, OutlierConfig,
+    RadiusFilter, StatisticalFilter,
+};
+pub use voxel::{hull_change, voxel_downsample, HullChange, VoxelGrid, VoxelMode};
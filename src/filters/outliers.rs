@@ -0,0 +1,227 @@
+use crate::error::{Error, Result};
+use crate::geometry::Point;
+use crate::kdtree::KdTree;
+use rayon::prelude::*;
+
+// statistical outlier removal: the points whose mean distance to their k nearest neighbors is
+// more than std_ratio standard deviations above the mean of all the points are removed.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct StatisticalFilter {
+    pub k: usize,
+    pub std_ratio: f64,
+}
+
+impl Default for StatisticalFilter {
+    fn default() -> Self {
+        StatisticalFilter {
+            k: 20,
+            std_ratio: 2.0,
+        }
+    }
+}
+
+// radius outlier removal: the points with fewer than min_neighbors other points within radius
+// are removed.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RadiusFilter {
+    pub radius: f64,
+    pub min_neighbors: usize,
+}
+
+// the outlier filters run after the soil removal, the statistical one first.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct OutlierConfig {
+    pub statistical: Option<StatisticalFilter>,
+    pub radius: Option<RadiusFilter>,
+}
+
+impl OutlierConfig {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(filter) = &self.statistical {
+            if filter.k == 0 {
+                return Err(Error::InvalidArgument(String::from(
+                    "statistical outlier removal needs at least 1 neighbor",
+                )));
+            }
+            if !(filter.std_ratio >= 0.0 && filter.std_ratio.is_finite()) {
+                return Err(Error::InvalidArgument(format!(
+                    "standard deviation ratio {} must be positive",
+                    filter.std_ratio
+                )));
+            }
+        }
+        if let Some(filter) = &self.radius {
+            if !(filter.radius > 0.0 && filter.radius.is_finite()) {
+                return Err(Error::InvalidArgument(format!(
+                    "radius {} must be positive",
+                    filter.radius
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+// remove the outliers, the other points keep their order.
+pub fn remove_outliers(points: Vec<Point>, config: &OutlierConfig) -> Result<Vec<Point>> {
+    config.validate()?;
+    let mut points = points;
+    if let Some(filter) = &config.statistical {
+        points = statistical_outlier_removal(points, filter);
+    }
+    if let Some(filter) = &config.radius {
+        points = radius_outlier_removal(points, filter);
+    }
+    Ok(points)
+}
+
+pub fn statistical_outlier_removal(points: Vec<Point>, filter: &StatisticalFilter) -> Vec<Point> {
+    if points.len() < 2 {
+        return points;
+    }
+    let k = filter.k.min(points.len() - 1);
+    let tree = KdTree::new(&points);
+    let mean_distances: Vec<f64> = points
+        .par_iter()
+        .map(|pt| {
+            // the nearest point is the point itself, at 0
+            let nearest = tree.nearest(pt, k + 1);
+            nearest.iter().map(|n| n.distance).sum::<f64>() / k as f64
+        })
+        .collect();
+    let count = mean_distances.len() as f64;
+    let mean = mean_distances.iter().sum::<f64>() / count;
+    let variance = mean_distances
+        .iter()
+        .map(|d| (d - mean).powi(2))
+        .sum::<f64>()
+        / count;
+    let cutoff = mean + filter.std_ratio * variance.sqrt();
+    keep(points, |i| mean_distances[i] <= cutoff)
+}
+
+pub fn radius_outlier_removal(points: Vec<Point>, filter: &RadiusFilter) -> Vec<Point> {
+    let tree = KdTree::new(&points);
+    let neighbor_counts: Vec<usize> = points
+        .par_iter()
+        // without the point itself
+        .map(|pt| tree.within_radius(pt, filter.radius).len() - 1)
+        .collect();
+    keep(points, |i| neighbor_counts[i] >= filter.min_neighbors)
+}
+
+fn keep<F: Fn(usize) -> bool>(points: Vec<Point>, is_kept: F) -> Vec<Point> {
+    points
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| is_kept(*i))
+        .map(|(_, pt)| pt)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{remove_outliers, OutlierConfig, RadiusFilter, StatisticalFilter};
+    use crate::geometry::Point;
+
+    // a 10 x 10 x 3 grid of spacing 0.1, and 2 flying pixels above it
+    fn canopy_with_noise() -> Vec<Point> {
+        let mut data = vec![];
+        for i in 0..10 {
+            for j in 0..10 {
+                for k in 0..3 {
+                    data.push(Point::new(i as f64 * 0.1, j as f64 * 0.1, k as f64 * 0.1));
+                }
+            }
+        }
+        data.push(Point::new(0.5, 0.5, 3.0));
+        data.push(Point::new(0.2, 0.7, 5.0));
+        data
+    }
+
+    fn assert_no_noise(result: &[Point]) {
+        assert_eq!(result.len(), 300);
+        assert!(result.iter().all(|pt| pt.z < 1.0));
+    }
+
+    #[test]
+    fn test_statistical_outlier_removal() {
+        let config = OutlierConfig {
+            statistical: Some(StatisticalFilter::default()),
+            radius: None,
+        };
+        let result = remove_outliers(canopy_with_noise(), &config).unwrap();
+        assert_no_noise(&result);
+        // the order is kept
+        assert_eq!(result[..], canopy_with_noise()[..300]);
+    }
+
+    #[test]
+    fn test_radius_outlier_removal() {
+        let config = OutlierConfig {
+            statistical: None,
+            radius: Some(RadiusFilter {
+                radius: 0.15,
+                min_neighbors: 2,
+            }),
+        };
+        assert_no_noise(&remove_outliers(canopy_with_noise(), &config).unwrap());
+        // every point has a neighbor within 1
+        let config = OutlierConfig {
+            statistical: None,
+            radius: Some(RadiusFilter {
+                radius: 1.0,
+                min_neighbors: 1,
+            }),
+        };
+        let data = vec![Point::new(0.0, 0.0, 0.0), Point::new(0.0, 0.0, 1.0)];
+        assert_eq!(remove_outliers(data, &config).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_outlier_removal_small_input() {
+        let config = OutlierConfig {
+            statistical: Some(StatisticalFilter::default()),
+            radius: None,
+        };
+        assert!(remove_outliers(vec![], &config).unwrap().is_empty());
+        let data = vec![Point::new(0.0, 0.0, 0.0), Point::new(1.0, 0.0, 0.0)];
+        assert_eq!(remove_outliers(data, &config).unwrap().len(), 2);
+        assert_eq!(
+            remove_outliers(canopy_with_noise(), &OutlierConfig::default())
+                .unwrap()
+                .len(),
+            302
+        );
+    }
+
+    #[test]
+    fn test_outlier_config_invalid() {
+        let invalid = [
+            OutlierConfig {
+                statistical: Some(StatisticalFilter {
+                    k: 0,
+                    std_ratio: 1.0,
+                }),
+                radius: None,
+            },
+            OutlierConfig {
+                statistical: Some(StatisticalFilter {
+                    k: 5,
+                    std_ratio: f64::NAN,
+                }),
+                radius: None,
+            },
+            OutlierConfig {
+                statistical: None,
+                radius: Some(RadiusFilter {
+                    radius: 0.0,
+                    min_neighbors: 1,
+                }),
+            },
+        ];
+        for config in invalid {
+            assert!(remove_outliers(canopy_with_noise(), &config).is_err());
+        }
+    }
+}
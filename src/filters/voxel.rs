@@ -0,0 +1,232 @@
+use crate::error::{Error, Result};
+use crate::geometry::Point;
+use crate::plant_traits::PlantTraits;
+use std::collections::BTreeMap;
+
+// what is kept of the points that fall in the same voxel.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum VoxelMode {
+    // one point, the centroid of the voxel points
+    Centroid,
+    // the points that are the farthest along the axes and the diagonals, up to 14 per voxel.
+    // the hull of the downsampled points stays close to the hull of all the points.
+    Extremes,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct VoxelGrid {
+    // edge of the cubic voxels
+    pub leaf_size: f64,
+    pub mode: VoxelMode,
+}
+
+impl VoxelGrid {
+    pub fn validate(&self) -> Result<()> {
+        if !(self.leaf_size > 0.0 && self.leaf_size.is_finite()) {
+            return Err(Error::InvalidArgument(format!(
+                "leaf size {} must be positive",
+                self.leaf_size
+            )));
+        }
+        Ok(())
+    }
+}
+
+// the directions the extreme points of a voxel are taken along
+const DIRECTIONS: [(f64, f64, f64); 14] = [
+    (1.0, 0.0, 0.0),
+    (-1.0, 0.0, 0.0),
+    (0.0, 1.0, 0.0),
+    (0.0, -1.0, 0.0),
+    (0.0, 0.0, 1.0),
+    (0.0, 0.0, -1.0),
+    (1.0, 1.0, 1.0),
+    (1.0, 1.0, -1.0),
+    (1.0, -1.0, 1.0),
+    (1.0, -1.0, -1.0),
+    (-1.0, 1.0, 1.0),
+    (-1.0, 1.0, -1.0),
+    (-1.0, -1.0, 1.0),
+    (-1.0, -1.0, -1.0),
+];
+
+// downsample the points on a grid of voxels aligned on the lowest corner of the points.
+// the voxels are in the order of their x, y, z index.
+pub fn voxel_downsample(points: &[Point], grid: &VoxelGrid) -> Result<Vec<Point>> {
+    grid.validate()?;
+    if points.is_empty() {
+        return Ok(vec![]);
+    }
+    let mut min = (f64::MAX, f64::MAX, f64::MAX);
+    for pt in points {
+        min = (min.0.min(pt.x), min.1.min(pt.y), min.2.min(pt.z));
+    }
+    let mut voxels: BTreeMap<(i64, i64, i64), Vec<usize>> = BTreeMap::new();
+    for (i, pt) in points.iter().enumerate() {
+        let key = (
+            ((pt.x - min.0) / grid.leaf_size).floor() as i64,
+            ((pt.y - min.1) / grid.leaf_size).floor() as i64,
+            ((pt.z - min.2) / grid.leaf_size).floor() as i64,
+        );
+        voxels.entry(key).or_default().push(i);
+    }
+
+    let mut result = Vec::with_capacity(voxels.len());
+    for indices in voxels.values() {
+        match grid.mode {
+            VoxelMode::Centroid => result.push(centroid(points, indices)),
+            VoxelMode::Extremes => {
+                let mut kept = extreme_indices(points, indices);
+                kept.sort_unstable();
+                kept.dedup();
+                result.extend(kept.iter().map(|i| points[*i]));
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn centroid(points: &[Point], indices: &[usize]) -> Point {
+    let (mut x, mut y, mut z) = (0.0, 0.0, 0.0);
+    for i in indices {
+        x += points[*i].x;
+        y += points[*i].y;
+        z += points[*i].z;
+    }
+    let count = indices.len() as f64;
+    Point::new(x / count, y / count, z / count)
+}
+
+// index of the farthest point along each direction, the first one on a tie.
+fn extreme_indices(points: &[Point], indices: &[usize]) -> Vec<usize> {
+    DIRECTIONS
+        .iter()
+        .map(|(dx, dy, dz)| {
+            let along = |i: &usize| dx * points[*i].x + dy * points[*i].y + dz * points[*i].z;
+            let mut best = indices[0];
+            for i in &indices[1..] {
+                if along(i) > along(&best) {
+                    best = *i;
+                }
+            }
+            best
+        })
+        .collect()
+}
+
+// traits of the points before and after the downsampling.
+#[derive(Copy, Clone, Debug)]
+pub struct HullChange {
+    pub before: PlantTraits,
+    pub after: PlantTraits,
+}
+
+impl HullChange {
+    // relative changes, (after - before) / before
+    pub fn canopy_area_change(&self) -> f64 {
+        relative_change(self.before.canopy_area, self.after.canopy_area)
+    }
+
+    pub fn surface_area_change(&self) -> f64 {
+        relative_change(self.before.hull_surface_area, self.after.hull_surface_area)
+    }
+
+    pub fn volume_change(&self) -> f64 {
+        relative_change(self.before.hull_volume, self.after.hull_volume)
+    }
+}
+
+fn relative_change(before: f64, after: f64) -> f64 {
+    if before == 0.0 {
+        return if after == 0.0 { 0.0 } else { f64::INFINITY };
+    }
+    (after - before) / before
+}
+
+// compute both hulls on the points before and after the downsampling.
+pub fn hull_change(points: &[Point], downsampled: &[Point]) -> Result<HullChange> {
+    Ok(HullChange {
+        before: PlantTraits::compute(points)?,
+        after: PlantTraits::compute(downsampled)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hull_change, voxel_downsample, VoxelGrid, VoxelMode};
+    use crate::geometry::Point;
+
+    // a 21 x 21 x 21 grid of spacing 0.05 in the unit cube
+    fn dense_cube() -> Vec<Point> {
+        let mut data = vec![];
+        for i in 0..21 {
+            for j in 0..21 {
+                for k in 0..21 {
+                    data.push(Point::new(
+                        i as f64 * 0.05,
+                        j as f64 * 0.05,
+                        k as f64 * 0.05,
+                    ));
+                }
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn test_voxel_centroid() {
+        let data = vec![
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(0.5, 0.5, 0.5),
+            Point::new(2.0, 0.0, 0.0),
+        ];
+        let grid = VoxelGrid {
+            leaf_size: 1.0,
+            mode: VoxelMode::Centroid,
+        };
+        let result = voxel_downsample(&data, &grid).unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].z, 0.25);
+        assert_eq!(result[0].x, 0.25);
+        assert_eq!(result[1].x, 2.0);
+    }
+
+    #[test]
+    fn test_voxel_extremes_keep_the_hull() {
+        let data = dense_cube();
+        let grid = VoxelGrid {
+            leaf_size: 0.3,
+            mode: VoxelMode::Extremes,
+        };
+        let result = voxel_downsample(&data, &grid).unwrap();
+        assert!(result.len() < data.len() / 4);
+        let change = hull_change(&data, &result).unwrap();
+        assert!((change.before.hull_volume - 1.0).abs() < 0.000001);
+        assert!(change.volume_change().abs() < 0.000001);
+        assert!(change.surface_area_change().abs() < 0.000001);
+        assert!(change.canopy_area_change().abs() < 0.000001);
+
+        // the centroids shrink the hull
+        let centroid = VoxelGrid {
+            leaf_size: 0.3,
+            mode: VoxelMode::Centroid,
+        };
+        let result = voxel_downsample(&data, &centroid).unwrap();
+        let change = hull_change(&data, &result).unwrap();
+        assert!(change.volume_change() < -0.1);
+    }
+
+    #[test]
+    fn test_voxel_invalid_leaf_size() {
+        let grid = VoxelGrid {
+            leaf_size: 0.0,
+            mode: VoxelMode::Centroid,
+        };
+        assert!(voxel_downsample(&dense_cube(), &grid).is_err());
+        let grid = VoxelGrid {
+            leaf_size: 1.0,
+            mode: VoxelMode::Centroid,
+        };
+        assert!(voxel_downsample(&[], &grid).unwrap().is_empty());
+    }
+}
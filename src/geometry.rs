@@ -2,7 +2,7 @@ use std::fmt::{Display, Formatter};
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Point {
     pub x: f64,
     pub y: f64,
@@ -15,11 +15,9 @@ impl Point {
     pub fn new(x: f64, y: f64, z: f64) -> Self {
         Point { x, y, z }
     }
-    #[allow(dead_code)]
     pub fn new2d(x: f64, y: f64) -> Self {
         Point { x, y, z: 0.0 }
     }
-    #[allow(dead_code)]
     pub fn new2d_int(x: i32, y: i32) -> Self {
         Point {
             x: x as f64,
@@ -31,7 +29,7 @@ impl Point {
 
 impl Clone for Point {
     fn clone(&self) -> Self {
-        Point::new(self.x, self.y, self.z)
+        *self
     }
 }
 
@@ -47,18 +45,17 @@ impl Display for Point {
     }
 }
 
-pub fn print_pts(data: &[Point]) {
-    for pt in data {
-        println!("{}", pt);
-    }
-}
-
-pub fn sort_points_by_x(data: &mut Vec<Point>) {
-    data.sort_by(|pt1, pt2| pt1.x.partial_cmp(&pt2.x).unwrap());
+// points with the same x are sorted by y.
+pub fn sort_points_by_x(data: &mut [Point]) {
+    data.sort_by(|pt1, pt2| {
+        pt1.x
+            .partial_cmp(&pt2.x)
+            .unwrap()
+            .then(pt1.y.partial_cmp(&pt2.y).unwrap())
+    });
 }
 
-#[allow(dead_code)]
-pub fn sort_points_by_y(data: &mut Vec<Point>) {
+pub fn sort_points_by_y(data: &mut [Point]) {
     data.sort_by(|pt1, pt2| pt1.y.partial_cmp(&pt2.y).unwrap());
 }
 
@@ -75,8 +72,7 @@ impl Triangle {
 }
 
 pub fn triangles_to_pts(data: Vec<Triangle>) -> Vec<Point> {
-    let mut result = vec![];
-    result.reserve(data.len() * 3);
+    let mut result = Vec::with_capacity(data.len() * 3);
     for tri in data {
         result.push(tri.p1);
         result.push(tri.p2);
@@ -102,7 +98,7 @@ impl PartialEq<Self> for Edge {
     }
 }
 
-#[derive(Copy, Clone, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 pub struct Vec3D {
     pub x: f64,
     pub y: f64,
@@ -114,3 +110,16 @@ impl Vec3D {
         Vec3D { x, y, z }
     }
 }
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl Color {
+    pub fn new(red: u8, green: u8, blue: u8) -> Self {
+        Color { red, green, blue }
+    }
+}
@@ -0,0 +1,319 @@
+use crate::geometry::Point;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+// ranges of at most this many points are searched by brute force
+const LEAF_SIZE: usize = 8;
+
+// static 3D k-d tree over points, built once from all the points.
+// the tree is implicit: the node of a range of `indices` is its middle element, and the range is
+// split on the axis of its largest spread. the queries return the indices of the points in the
+// slice the tree was built from.
+pub struct KdTree {
+    points: Vec<Point>,
+    indices: Vec<usize>,
+    // split axis of the node at this position of indices, only set for the middle of a range
+    axes: Vec<u8>,
+}
+
+// a point found by a query, with its distance to the query point.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Neighbor {
+    pub index: usize,
+    pub distance: f64,
+}
+
+impl KdTree {
+    pub fn new(points: &[Point]) -> KdTree {
+        let mut tree = KdTree {
+            points: points.to_vec(),
+            indices: (0..points.len()).collect(),
+            axes: vec![0; points.len()],
+        };
+        tree.build(0, points.len());
+        tree
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn point(&self, index: usize) -> &Point {
+        &self.points[index]
+    }
+
+    fn build(&mut self, start: usize, end: usize) {
+        if end - start <= LEAF_SIZE {
+            return;
+        }
+        let axis = self.widest_axis(start, end);
+        let mid = start + (end - start) / 2;
+        let points = &self.points;
+        self.indices[start..end].select_nth_unstable_by(mid - start, |a, b| {
+            coord(&points[*a], axis).total_cmp(&coord(&points[*b], axis))
+        });
+        self.axes[mid] = axis as u8;
+        self.build(start, mid);
+        self.build(mid + 1, end);
+    }
+
+    fn widest_axis(&self, start: usize, end: usize) -> usize {
+        let mut min = [f64::MAX; 3];
+        let mut max = [f64::MIN; 3];
+        for i in &self.indices[start..end] {
+            for axis in 0..3 {
+                let val = coord(&self.points[*i], axis);
+                min[axis] = min[axis].min(val);
+                max[axis] = max[axis].max(val);
+            }
+        }
+        let spread = |axis: usize| max[axis] - min[axis];
+        (0..3)
+            .max_by(|a, b| spread(*a).total_cmp(&spread(*b)))
+            .unwrap()
+    }
+
+    // the node of a range of more than LEAF_SIZE points: its position, and the signed distance
+    // of the query to the split plane.
+    fn split(&self, query: &Point, start: usize, end: usize) -> (usize, f64) {
+        let mid = start + (end - start) / 2;
+        let axis = self.axes[mid] as usize;
+        let value = coord(&self.points[self.indices[mid]], axis);
+        (mid, coord(query, axis) - value)
+    }
+
+    // the k nearest points, the nearest first. the query point itself is included if it is in
+    // the tree.
+    pub fn nearest(&self, query: &Point, k: usize) -> Vec<Neighbor> {
+        if k == 0 {
+            return vec![];
+        }
+        let mut heap = BinaryHeap::with_capacity(k + 1);
+        self.nearest_in(query, k, 0, self.len(), &mut heap);
+        let mut result: Vec<Neighbor> = heap
+            .into_iter()
+            .map(|c| Neighbor {
+                index: c.index,
+                distance: c.distance_sq.sqrt(),
+            })
+            .collect();
+        result.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        result
+    }
+
+    fn nearest_in(
+        &self,
+        query: &Point,
+        k: usize,
+        start: usize,
+        end: usize,
+        heap: &mut BinaryHeap<Candidate>,
+    ) {
+        if end - start <= LEAF_SIZE {
+            for i in &self.indices[start..end] {
+                push_candidate(heap, k, *i, distance_sq(query, &self.points[*i]));
+            }
+            return;
+        }
+        let (mid, diff) = self.split(query, start, end);
+        let mid_index = self.indices[mid];
+        push_candidate(
+            heap,
+            k,
+            mid_index,
+            distance_sq(query, &self.points[mid_index]),
+        );
+        let (near, far) = if diff < 0.0 {
+            ((start, mid), (mid + 1, end))
+        } else {
+            ((mid + 1, end), (start, mid))
+        };
+        self.nearest_in(query, k, near.0, near.1, heap);
+        let worst = heap.peek().map(|c| c.distance_sq).unwrap_or(f64::MAX);
+        if heap.len() < k || diff * diff < worst {
+            self.nearest_in(query, k, far.0, far.1, heap);
+        }
+    }
+
+    // the points within radius (inclusive), in no particular order.
+    pub fn within_radius(&self, query: &Point, radius: f64) -> Vec<Neighbor> {
+        let mut result = vec![];
+        self.within_radius_in(query, radius * radius, 0, self.len(), &mut result);
+        result
+    }
+
+    fn within_radius_in(
+        &self,
+        query: &Point,
+        radius_sq: f64,
+        start: usize,
+        end: usize,
+        result: &mut Vec<Neighbor>,
+    ) {
+        let mut push = |i: usize| {
+            let d = distance_sq(query, &self.points[i]);
+            if d <= radius_sq {
+                result.push(Neighbor {
+                    index: i,
+                    distance: d.sqrt(),
+                });
+            }
+        };
+        if end - start <= LEAF_SIZE {
+            for i in &self.indices[start..end] {
+                push(*i);
+            }
+            return;
+        }
+        let (mid, diff) = self.split(query, start, end);
+        push(self.indices[mid]);
+        // the points on the split plane can be on both sides
+        if diff <= 0.0 || diff * diff <= radius_sq {
+            self.within_radius_in(query, radius_sq, start, mid, result);
+        }
+        if diff >= 0.0 || diff * diff <= radius_sq {
+            self.within_radius_in(query, radius_sq, mid + 1, end, result);
+        }
+    }
+}
+
+fn coord(pt: &Point, axis: usize) -> f64 {
+    match axis {
+        0 => pt.x,
+        1 => pt.y,
+        _ => pt.z,
+    }
+}
+
+fn distance_sq(a: &Point, b: &Point) -> f64 {
+    (a.x - b.x).powi(2) + (a.y - b.y).powi(2) + (a.z - b.z).powi(2)
+}
+
+// max-heap entry, the farthest of the k candidates is on top
+#[derive(Copy, Clone, PartialEq)]
+struct Candidate {
+    distance_sq: f64,
+    index: usize,
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance_sq
+            .total_cmp(&other.distance_sq)
+            .then(self.index.cmp(&other.index))
+    }
+}
+
+fn push_candidate(heap: &mut BinaryHeap<Candidate>, k: usize, index: usize, distance_sq: f64) {
+    let candidate = Candidate { distance_sq, index };
+    if heap.len() < k {
+        heap.push(candidate);
+    } else if candidate < *heap.peek().unwrap() {
+        heap.pop();
+        heap.push(candidate);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{distance_sq, KdTree};
+    use crate::geometry::Point;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn random_points(count: usize, seed: u64) -> Vec<Point> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..count)
+            .map(|_| {
+                Point::new(
+                    rng.gen_range(0.0..1.0),
+                    rng.gen_range(0.0..2.0),
+                    rng.gen_range(0.0..0.5),
+                )
+            })
+            .collect()
+    }
+
+    fn brute_force_distances(points: &[Point], query: &Point) -> Vec<f64> {
+        let mut distances: Vec<f64> = points
+            .iter()
+            .map(|pt| distance_sq(query, pt).sqrt())
+            .collect();
+        distances.sort_by(|a, b| a.total_cmp(b));
+        distances
+    }
+
+    #[test]
+    fn test_kdtree_nearest_brute_force() {
+        let points = random_points(2000, 1);
+        let tree = KdTree::new(&points);
+        assert_eq!(tree.len(), 2000);
+        for query in random_points(50, 2).iter().chain(&points[..50]) {
+            let expected = brute_force_distances(&points, query);
+            for k in [1, 5, 30] {
+                let result = tree.nearest(query, k);
+                assert_eq!(result.len(), k);
+                for (neighbor, distance) in result.iter().zip(&expected) {
+                    assert_eq!(neighbor.distance, *distance);
+                    assert_eq!(
+                        distance_sq(query, tree.point(neighbor.index)).sqrt(),
+                        neighbor.distance
+                    );
+                }
+            }
+        }
+        // more than the points
+        assert_eq!(tree.nearest(&points[0], 5000).len(), 2000);
+        assert!(tree.nearest(&points[0], 0).is_empty());
+    }
+
+    #[test]
+    fn test_kdtree_radius_brute_force() {
+        let points = random_points(2000, 3);
+        let tree = KdTree::new(&points);
+        for query in random_points(50, 4).iter() {
+            for radius in [0.0, 0.05, 0.2, 3.0] {
+                let mut result: Vec<usize> = tree
+                    .within_radius(query, radius)
+                    .iter()
+                    .map(|n| n.index)
+                    .collect();
+                result.sort_unstable();
+                let expected: Vec<usize> = (0..points.len())
+                    .filter(|i| distance_sq(query, &points[*i]) <= radius * radius)
+                    .collect();
+                assert_eq!(result, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_kdtree_duplicates_and_small() {
+        let empty = KdTree::new(&[]);
+        assert!(empty.is_empty());
+        assert!(empty.nearest(&Point::new(0.0, 0.0, 0.0), 3).is_empty());
+        assert!(empty
+            .within_radius(&Point::new(0.0, 0.0, 0.0), 1.0)
+            .is_empty());
+
+        // the same point many times, split on an axis without spread
+        let points = vec![Point::new(1.0, 1.0, 1.0); 100];
+        let tree = KdTree::new(&points);
+        assert_eq!(tree.within_radius(&points[0], 0.0).len(), 100);
+        let nearest = tree.nearest(&Point::new(1.0, 1.0, 2.0), 10);
+        assert_eq!(nearest.len(), 10);
+        assert!(nearest.iter().all(|n| n.distance == 1.0));
+    }
+}
@@ -0,0 +1,43 @@
+// Convex hulls and phenotyping traits of plant point clouds.
+
+// alpha shapes, a tighter plant volume than the convex hull
+pub mod alpha_shape;
+// the pipeline on many files in parallel
+pub mod batch;
+// splits a scan into plants
+pub mod clustering;
+// 2D convex hulls and the concave hull of the canopy outline
+pub mod convex_hull_2d;
+// 3D convex hulls, their area and volume
+pub mod convex_hull_3d;
+// CSV output
+pub mod csv_file;
+// 3D tetrahedralization, 2D triangulation and Voronoi diagram of the top view
+pub mod delaunay;
+// the error type of the crate
+pub mod error;
+// scores the soil removal against hand-labelled clouds
+pub mod evaluation;
+// outlier removal and voxel downsampling
+pub mod filters;
+// the point and triangle types
+pub mod geometry;
+// k-nearest and radius queries
+pub mod kdtree;
+// from a scan to the traits of its plants
+pub mod pipeline;
+// the traits of one plant
+pub mod plant_traits;
+// PLY input and output
+pub mod ply_file;
+// exact orientation tests
+pub mod predicates;
+// soil removal by height, plane, edges or color
+pub mod soil_removal;
+// JSON output
+pub mod to_json;
+
+// the most used types
+pub use error::{Error, Result};
+pub use geometry::{Color, Point, Triangle, Vec3D};
+pub use plant_traits::PlantTraits;
@@ -1,51 +1,393 @@
-use crate::geometry::triangles_to_pts;
+use crate::cli::{
+    Cli, ClusterArgs, Command, Hull2dArgs, Hull3dArgs, OutlierArgs, SoilArgs, VoxelArgs,
+};
+use clap::Parser;
+use final_project::alpha_shape::alpha_shape;
+use final_project::batch;
+use final_project::convex_hull_3d::HullMesh;
+use final_project::evaluation::{self, Method};
+use final_project::filters::{self, OutlierConfig, VoxelGrid};
+use final_project::pipeline::{self, PipelineConfig, PipelineHooks, Plant};
+use final_project::ply_file::PlyPoint;
+use final_project::soil_removal::Plane;
+use final_project::{convex_hull_2d, convex_hull_3d, delaunay, ply_file, to_json};
+use final_project::{Error, Point, Result, Triangle};
+use std::path::Path;
 
-mod convex_hull_2d;
-mod convex_hull_3d;
-mod geometry;
-mod ply_file;
-mod soil_removal;
-mod to_json;
+mod cli;
 
 fn main() {
-    let ply = ply_file::read_ply_file(get_input_file_path());
-    let input_data = match ply_file::ply_to_pts(ply) {
-        Ok(val) => val,
-        Err(err) => {
-            println!("{}", err.as_str());
-            return;
+    let cli = Cli::parse();
+    if let Err(err) = run(cli.command) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
+
+fn run(command: Command) -> Result<()> {
+    match command {
+        Command::Hull2d {
+            input,
+            output,
+            hull,
+        } => {
+            let points = read_points(input)?;
+            let hull = hull_2d(points, &hull)?;
+            println!("CH pt count: {}", hull.len());
+            ply_file::write_to_ply_file(output, &hull)
+        }
+        Command::Hull3d {
+            input,
+            output,
+            hull,
+        } => {
+            let points = read_points(input)?;
+            // with alpha, the alpha shape instead of the convex hull
+            if let Some(alpha) = hull.alpha {
+                let shape = alpha_shape(&points, alpha)?;
+                println!("alpha shape volume: {}", shape.volume);
+                return ply_file::write_mesh_to_ply_file(output, &shape.mesh);
+            }
+            let hull = hull_3d(points, &hull)?;
+            ply_file::write_mesh_to_ply_file(output, &HullMesh::from_triangles(&hull))
+        }
+        Command::Delaunay {
+            input,
+            output,
+            voronoi,
+        } => {
+            let points = read_points(input)?;
+            let triangulation = delaunay::delaunay_2d(&points)?;
+            println!("{} triangles", triangulation.len());
+            let faces: Vec<Vec<u32>> = triangulation
+                .triangles
+                .iter()
+                .map(|tri| tri.iter().map(|v| *v as u32).collect())
+                .collect();
+            ply_file::write_faces_to_ply_file(output, &points, &faces)?;
+            if let Some(path) = voronoi {
+                let voronoi = delaunay::Voronoi::new(&triangulation, &points);
+                let cells = voronoi.polygons();
+                println!("{} bounded Voronoi cells", cells.len());
+                ply_file::write_faces_to_ply_file(path, &voronoi.vertices, &cells)?;
+            }
+            Ok(())
+        }
+        Command::Soil {
+            input,
+            output,
+            soil,
+        } => {
+            let points = read_ply_points(input)?;
+            let segmentation =
+                pipeline::remove_soil(points, &soil.config(), soil.color_config().as_ref())?;
+            report_soil(&segmentation.plant, segmentation.ground_plane.as_ref());
+            ply_file::write_to_ply_file(output, &segmentation.plant)
+        }
+        Command::Traits {
+            input,
+            soil,
+            outliers,
+            voxel,
+            clusters,
+            hull_2d,
+            hull_3d,
+            soil_output,
+            plant_output,
+            hull2d_output,
+            hull3d_output,
+            alpha_output,
+            json,
+            input_json,
+            no_json,
+        } => {
+            let input_data = read_ply_points(input)?;
+            if !no_json {
+                let points: Vec<Point> = input_data.iter().map(|pt| pt.point).collect();
+                to_json::dump_to_json(&points, &input_json)?;
+            }
+            let config = pipeline_config(&soil, &outliers, &voxel, &clusters, &hull_2d, &hull_3d);
+            let mut outputs = TraitsOutputs {
+                soil_output,
+                plant_output,
+                hull2d_output,
+                hull3d_output,
+                alpha_output,
+                json: (!no_json).then_some(json),
+                filtered: config.outliers != OutlierConfig::default() || config.voxel.is_some(),
+            };
+            pipeline::process_points(input_data, &config, &mut outputs)?;
+            Ok(())
+        }
+        Command::Batch {
+            input,
+            output,
+            soil,
+            outliers,
+            voxel,
+            clusters,
+            hull_2d,
+            hull_3d,
+            threads,
+        } => {
+            let paths = batch::find_ply_files(&input)?;
+            println!("{} PLY files", paths.len());
+            let config = pipeline_config(&soil, &outliers, &voxel, &clusters, &hull_2d, &hull_3d);
+            let rows = with_threads(threads, || batch::process_files(&paths, &config))?;
+            for row in &rows {
+                if let Some(err) = &row.error {
+                    eprintln!("{}: {}", row.path, err);
+                }
+            }
+            let failed = rows.iter().filter(|row| row.error.is_some()).count();
+            println!("{} plants, {} failed", rows.len() - failed, failed);
+            if output.to_lowercase().ends_with(".json") {
+                to_json::dump_batch_to_json(&rows, &output)
+            } else {
+                batch::write_csv(&rows, &output)
+            }
+        }
+        Command::Compare {
+            input,
+            output,
+            methods,
+            soil_label,
+            threads,
+        } => {
+            let paths = batch::find_ply_files(&input)?;
+            println!("{} PLY files", paths.len());
+            let methods: Vec<Method> = methods.iter().map(|method| method.method()).collect();
+            let rows = with_threads(threads, || {
+                evaluation::evaluate_files(&paths, &methods, soil_label)
+            })?;
+            for row in &rows {
+                if let Some(err) = &row.error {
+                    eprintln!("{} ({}): {}", row.path, row.method, err);
+                }
+            }
+            println!("method\tprecision\trecall\tiou\tf1");
+            for (name, metrics) in evaluation::summarize(&rows, &methods) {
+                match metrics {
+                    Some(m) => println!(
+                        "{}\t{:.4}\t{:.4}\t{:.4}\t{:.4}",
+                        name,
+                        m.precision(),
+                        m.recall(),
+                        m.iou(),
+                        m.f1()
+                    ),
+                    None => println!("{}\tfailed on every file", name),
+                }
+            }
+            match output {
+                Some(path) => evaluation::write_csv(&rows, &path),
+                None => Ok(()),
+            }
+        }
+        Command::Downsample {
+            input,
+            output,
+            leaf_size,
+            voxel_mode,
+            no_report,
+        } => {
+            let points = read_points(input)?;
+            let grid = VoxelGrid {
+                leaf_size,
+                mode: voxel_mode.mode(),
+            };
+            let downsampled = filters::voxel_downsample(&points, &grid)?;
+            println!("downsampled len: {}", downsampled.len());
+            if !no_report {
+                let change = filters::hull_change(&points, &downsampled)?;
+                println!("\tbefore\tafter\tchange");
+                println!(
+                    "canopy area\t{}\t{}\t{:+.2}%",
+                    change.before.canopy_area,
+                    change.after.canopy_area,
+                    change.canopy_area_change() * 100.0
+                );
+                println!(
+                    "hull surface area\t{}\t{}\t{:+.2}%",
+                    change.before.hull_surface_area,
+                    change.after.hull_surface_area,
+                    change.surface_area_change() * 100.0
+                );
+                println!(
+                    "hull volume\t{}\t{}\t{:+.2}%",
+                    change.before.hull_volume,
+                    change.after.hull_volume,
+                    change.volume_change() * 100.0
+                );
+            }
+            ply_file::write_to_ply_file(output, &downsampled)
+        }
+        Command::Convert { input, output } => {
+            let points = read_points(input)?;
+            ply_file::write_to_ply_file(output, &points)
+        }
+        Command::Bench {
+            input,
+            algorithm,
+            runs,
+        } => {
+            let points = read_points(input)?;
+            let algorithm = algorithm.algorithm();
+            let runs = convex_hull_2d::run_benchmark_with(&points, algorithm, runs)?;
+            for (hull_len, duration) in runs {
+                println!("=====");
+                println!("CH pt count\t{}", hull_len);
+                println!("{:?} time\t{}", algorithm, duration.as_nanos());
+            }
+            Ok(())
         }
+    }
+}
+
+// run f on a pool of this many threads, or on the global pool.
+fn with_threads<T: Send, F: FnOnce() -> T + Send>(threads: Option<usize>, f: F) -> Result<T> {
+    let count = match threads {
+        None => return Ok(f()),
+        Some(val) => val,
     };
+    match rayon::ThreadPoolBuilder::new().num_threads(count).build() {
+        Ok(pool) => Ok(pool.install(f)),
+        Err(err) => Err(Error::InvalidArgument(format!(
+            "cannot start {} threads, {}",
+            count, err
+        ))),
+    }
+}
+
+fn read_points(path: String) -> Result<Vec<Point>> {
+    println!("path: {}", path);
+    let ply = ply_file::read_ply_file(path)?;
+    let points = ply_file::ply_to_pts(ply)?;
+    println!("points in input ply: {}", points.len());
+    Ok(points)
+}
+
+// the points with their colors, for the color based soil removal.
+fn read_ply_points(path: String) -> Result<Vec<PlyPoint>> {
+    println!("path: {}", path);
+    let ply = ply_file::read_ply_file(path)?;
+    let points = ply_file::ply_to_ply_points(ply)?;
+    println!("points in input ply: {}", points.len());
+    Ok(points)
+}
 
-    to_json::dump_to_json(&input_data);
+fn hull_2d(points: Vec<Point>, args: &Hull2dArgs) -> Result<Vec<Point>> {
+    if let Some(k) = args.concave_k {
+        return convex_hull_2d::concave_hull(&points, k);
+    }
+    convex_hull_2d::convex_hull_with(points, args.algorithm.algorithm())
+}
 
-    println!("points in input ply: {}", input_data.len());
-    // geometry::print_pts(&input_data);
-    let soil_removed = soil_removal::remove_soil_simple(input_data);
-    println!("soil removed len: {}", soil_removed.len());
-    ply_file::write_to_ply_file(String::from("soil_removed.ply"), &soil_removed);
+fn hull_3d(points: Vec<Point>, args: &Hull3dArgs) -> Result<Vec<Triangle>> {
+    convex_hull_3d::convex_hull_with(points, args.algorithm.algorithm())
+}
 
-    // println!("2d convex hull");
-    // let hull = convex_hull_2d::convex_hull(soil_removed);
-    // let area = convex_hull_2d::convex_hull_area(&hull);
-    // println!("area: {}", area);
-    // ply_file::write_to_ply_file(String::from("output.ply"), &hull);
+fn pipeline_config(
+    soil: &SoilArgs,
+    outliers: &OutlierArgs,
+    voxel: &VoxelArgs,
+    clusters: &ClusterArgs,
+    hull_2d: &Hull2dArgs,
+    hull_3d: &Hull3dArgs,
+) -> PipelineConfig {
+    PipelineConfig {
+        soil: soil.config(),
+        color: soil.color_config(),
+        outliers: outliers.config(),
+        voxel: voxel.grid(),
+        clusters: clusters.config(),
+        algorithm_2d: hull_2d.algorithm.algorithm(),
+        concave_k: hull_2d.concave_k,
+        algorithm_3d: hull_3d.algorithm.algorithm(),
+        alpha: hull_3d.alpha,
+    }
+}
 
-    println!("3d convex hull");
-    let hull_3d = convex_hull_3d::convex_hull(soil_removed);
-    for tri in &hull_3d {
-        println!("{}, {}, {}", tri.p1, tri.p2, tri.p3);
+fn report_soil(plant: &[Point], ground_plane: Option<&Plane>) {
+    if let Some(plane) = ground_plane {
+        println!(
+            "ground plane: {}\ttilt: {} degrees",
+            plane,
+            plane.tilt_degrees()
+        );
     }
-    ply_file::write_to_ply_file(String::from("output-3d.ply"), &triangles_to_pts(hull_3d));
+    println!("soil removed len: {}", plant.len());
+}
+
+// the files written by the traits command, and its progress.
+struct TraitsOutputs {
+    soil_output: Option<String>,
+    plant_output: Option<String>,
+    hull2d_output: Option<String>,
+    hull3d_output: Option<String>,
+    alpha_output: Option<String>,
+    // None with --no-json
+    json: Option<String>,
+    // the outlier removal or the downsampling changed the points
+    filtered: bool,
 }
 
-fn get_input_file_path() -> String {
-    let args: Vec<String> = std::env::args().collect();
-    let path = if args.len() >= 2 {
-        args.get(1).unwrap().clone()
-    } else {
-        String::from("data/lettuce.ply")
+impl PipelineHooks for TraitsOutputs {
+    fn soil_removed(&mut self, points: &[Point], ground_plane: Option<&Plane>) -> Result<()> {
+        report_soil(points, ground_plane);
+        match &self.soil_output {
+            Some(path) => ply_file::write_to_ply_file(path.clone(), points),
+            None => Ok(()),
+        }
+    }
+
+    fn filtered(&mut self, points: &[Point]) -> Result<()> {
+        if self.filtered {
+            println!("filtered len: {}", points.len());
+        }
+        Ok(())
+    }
+
+    fn plant(&mut self, index: Option<usize>, plant: &Plant) -> Result<()> {
+        // with clustering, every output file gets the index of the plant
+        let output = |path: &str| match index {
+            Some(i) => indexed_path(path, i),
+            None => String::from(path),
+        };
+        if let Some(i) = index {
+            println!("plant {}", i);
+        }
+        if let Some(path) = &self.plant_output {
+            ply_file::write_to_ply_file(output(path), &plant.points)?;
+        }
+        if let Some(path) = &self.hull2d_output {
+            ply_file::write_to_ply_file(output(path), &plant.hull_2d)?;
+        }
+        if let Some(path) = &self.hull3d_output {
+            let mesh = HullMesh::from_triangles(&plant.hull_3d);
+            ply_file::write_mesh_to_ply_file(output(path), &mesh)?;
+        }
+        if let (Some(path), Some(shape)) = (&self.alpha_output, &plant.alpha_shape) {
+            ply_file::write_mesh_to_ply_file(output(path), &shape.mesh)?;
+        }
+        println!("{}", plant.traits);
+        match &self.json {
+            Some(path) => to_json::dump_traits_to_json(&plant.traits, &output(path)),
+            None => Ok(()),
+        }
+    }
+}
+
+// plant.ply -> plant_2.ply
+fn indexed_path(path: &str, index: usize) -> String {
+    let path = Path::new(path);
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let name = match path.extension() {
+        Some(ext) => format!("{}_{}.{}", stem, index, ext.to_string_lossy()),
+        None => format!("{}_{}", stem, index),
     };
-    println!("path: {}", path);
-    path
+    path.with_file_name(name).display().to_string()
 }
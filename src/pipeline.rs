@@ -0,0 +1,269 @@
+use crate::alpha_shape::{alpha_shape, AlphaShape};
+use crate::clustering::{self, ClusterConfig};
+use crate::error::{Error, Result};
+use crate::filters::{self, OutlierConfig, VoxelGrid};
+use crate::geometry::{Point, Triangle};
+use crate::plant_traits::PlantTraits;
+use crate::ply_file::{self, PlyPoint};
+use crate::soil_removal::{self, ColorConfig, Plane, SoilRemovalConfig, SoilSegmentation};
+use crate::{convex_hull_2d, convex_hull_3d};
+use std::path::Path;
+
+// the pipeline from a scan to the traits of its plants: soil removal, outlier removal, voxel
+// downsampling, clustering into plants, then the hulls and the traits of every plant.
+// the `traits` command runs it on one file, `batch` on many files in parallel.
+#[derive(Copy, Clone, Debug)]
+pub struct PipelineConfig {
+    pub soil: SoilRemovalConfig,
+    // remove the soil by color instead, the PLY files must have colors
+    pub color: Option<ColorConfig>,
+    // outlier removal between the soil removal and the hulls
+    pub outliers: OutlierConfig,
+    // voxel downsampling after the outlier removal
+    pub voxel: Option<VoxelGrid>,
+    // split the points into plants, with the traits of each plant
+    pub clusters: Option<ClusterConfig>,
+    pub algorithm_2d: convex_hull_2d::Algorithm,
+    // k of the concave hull for the canopy area, instead of the 2D convex hull
+    pub concave_k: Option<usize>,
+    pub algorithm_3d: convex_hull_3d::Algorithm,
+    // alpha of the alpha shape, its volume is reported next to the hull volume
+    pub alpha: Option<f64>,
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        PipelineConfig {
+            soil: SoilRemovalConfig::default(),
+            color: None,
+            outliers: OutlierConfig::default(),
+            voxel: None,
+            clusters: None,
+            algorithm_2d: convex_hull_2d::Algorithm::Chan,
+            concave_k: None,
+            algorithm_3d: convex_hull_3d::Algorithm::QuickHull,
+            alpha: None,
+        }
+    }
+}
+
+// one plant, with the hulls its traits are computed from.
+#[derive(Clone)]
+pub struct Plant {
+    pub points: Vec<Point>,
+    // the concave hull with concave_k
+    pub hull_2d: Vec<Point>,
+    pub hull_3d: Vec<Triangle>,
+    // only with alpha
+    pub alpha_shape: Option<AlphaShape>,
+    pub traits: PlantTraits,
+}
+
+// called while the pipeline runs, to write the intermediate points or to report the progress.
+// the default methods do nothing, an error stops the pipeline.
+pub trait PipelineHooks {
+    // after the soil removal, with the ground plane of the RANSAC mode
+    fn soil_removed(&mut self, _points: &[Point], _ground_plane: Option<&Plane>) -> Result<()> {
+        Ok(())
+    }
+
+    // after the outlier removal and the downsampling, before the clustering
+    fn filtered(&mut self, _points: &[Point]) -> Result<()> {
+        Ok(())
+    }
+
+    // every plant once its traits are computed, index is the plant number with clustering
+    fn plant(&mut self, _index: Option<usize>, _plant: &Plant) -> Result<()> {
+        Ok(())
+    }
+}
+
+// no hooks, only the traits
+impl PipelineHooks for () {}
+
+// the traits of the plant, or of every plant with clustering (the largest first).
+pub fn process_file(
+    path: &Path,
+    config: &PipelineConfig,
+    hooks: &mut impl PipelineHooks,
+) -> Result<Vec<PlantTraits>> {
+    let ply = ply_file::read_ply_file(path.display().to_string())?;
+    process_points(ply_file::ply_to_ply_points(ply)?, config, hooks)
+}
+
+pub fn process_points(
+    points: Vec<PlyPoint>,
+    config: &PipelineConfig,
+    hooks: &mut impl PipelineHooks,
+) -> Result<Vec<PlantTraits>> {
+    let segmentation = remove_soil(points, &config.soil, config.color.as_ref())?;
+    hooks.soil_removed(&segmentation.plant, segmentation.ground_plane.as_ref())?;
+    let mut points = filters::remove_outliers(segmentation.plant, &config.outliers)?;
+    if let Some(grid) = &config.voxel {
+        points = filters::voxel_downsample(&points, grid)?;
+    }
+    hooks.filtered(&points)?;
+    let plants = match &config.clusters {
+        None => vec![points],
+        Some(clusters) => {
+            let plants = clustering::euclidean_clusters(&points, clusters)?;
+            if plants.is_empty() {
+                return Err(Error::Degenerate(format!(
+                    "no plant of at least {} points",
+                    clusters.min_points
+                )));
+            }
+            plants
+        }
+    };
+    let clustered = config.clusters.is_some();
+    let mut result = Vec::with_capacity(plants.len());
+    for (i, points) in plants.into_iter().enumerate() {
+        let plant = plant(points, config)?;
+        hooks.plant(clustered.then_some(i), &plant)?;
+        result.push(plant.traits);
+    }
+    Ok(result)
+}
+
+// remove the soil by color when it is given, by the soil config otherwise.
+pub fn remove_soil(
+    points: Vec<PlyPoint>,
+    soil: &SoilRemovalConfig,
+    color: Option<&ColorConfig>,
+) -> Result<SoilSegmentation> {
+    match color {
+        Some(color) => Ok(SoilSegmentation {
+            plant: soil_removal::remove_soil_by_color(points, color)?,
+            ground_plane: None,
+        }),
+        None => soil_removal::segment_soil(points.into_iter().map(|pt| pt.point).collect(), soil),
+    }
+}
+
+fn plant(points: Vec<Point>, config: &PipelineConfig) -> Result<Plant> {
+    let hull_2d = match config.concave_k {
+        Some(k) => convex_hull_2d::concave_hull(&points, k)?,
+        None => convex_hull_2d::convex_hull_with(points.clone(), config.algorithm_2d)?,
+    };
+    let hull_3d = convex_hull_3d::convex_hull_with(points.clone(), config.algorithm_3d)?;
+    let mut traits = PlantTraits::new(&points, &hull_2d, &hull_3d);
+    let alpha_shape = match config.alpha {
+        Some(alpha) => Some(alpha_shape(&points, alpha)?),
+        None => None,
+    };
+    traits.alpha_volume = alpha_shape.as_ref().map(|shape| shape.volume);
+    Ok(Plant {
+        points,
+        hull_2d,
+        hull_3d,
+        alpha_shape,
+        traits,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{process_points, PipelineConfig, PipelineHooks, Plant};
+    use crate::clustering::ClusterConfig;
+    use crate::error::{Error, Result};
+    use crate::geometry::Point;
+    use crate::ply_file::PlyPoint;
+    use crate::soil_removal::Plane;
+    use std::collections::BTreeMap;
+
+    // a box of 2 x 3 x 4 above a thin layer of soil, twice, 10 apart along x
+    fn two_plants() -> Vec<PlyPoint> {
+        let mut data = vec![];
+        for offset in [0.0, 10.0] {
+            for i in 0..5 {
+                for j in 0..5 {
+                    data.push(Point::new(offset + i as f64 * 0.5, j as f64 * 0.75, 0.0));
+                }
+            }
+            for x in [0.0, 2.0] {
+                for y in [0.0, 3.0] {
+                    for z in [6.0, 10.0] {
+                        data.push(Point::new(offset + x, y, z));
+                    }
+                }
+            }
+        }
+        data.into_iter()
+            .map(|point| PlyPoint {
+                point,
+                color: None,
+                normal: None,
+                intensity: None,
+                properties: BTreeMap::new(),
+            })
+            .collect()
+    }
+
+    #[derive(Default)]
+    struct Record {
+        soil_removed: usize,
+        filtered: usize,
+        plants: Vec<(Option<usize>, usize)>,
+    }
+
+    impl PipelineHooks for Record {
+        fn soil_removed(&mut self, points: &[Point], _: Option<&Plane>) -> Result<()> {
+            self.soil_removed = points.len();
+            Ok(())
+        }
+
+        fn filtered(&mut self, points: &[Point]) -> Result<()> {
+            self.filtered = points.len();
+            Ok(())
+        }
+
+        fn plant(&mut self, index: Option<usize>, plant: &Plant) -> Result<()> {
+            self.plants.push((index, plant.hull_3d.len()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_pipeline_hooks() {
+        let mut record = Record::default();
+        let traits = process_points(two_plants(), &PipelineConfig::default(), &mut record).unwrap();
+        assert_eq!(traits.len(), 1);
+        assert_eq!(traits[0].point_count, 16);
+        assert_eq!((record.soil_removed, record.filtered), (16, 16));
+        assert_eq!(record.plants, vec![(None, 12)]);
+
+        let config = PipelineConfig {
+            clusters: Some(ClusterConfig {
+                tolerance: 5.0,
+                min_points: 4,
+            }),
+            alpha: Some(100.0),
+            ..PipelineConfig::default()
+        };
+        let mut record = Record::default();
+        let traits = process_points(two_plants(), &config, &mut record).unwrap();
+        assert_eq!(traits.len(), 2);
+        for plant in &traits {
+            assert!((plant.hull_volume - 24.0).abs() < 0.000001);
+            assert!((plant.alpha_volume.unwrap() - 24.0).abs() < 0.000001);
+        }
+        assert_eq!(record.plants, vec![(Some(0), 12), (Some(1), 12)]);
+    }
+
+    #[test]
+    fn test_pipeline_no_plant() {
+        // every cluster is too small, an error rather than no traits
+        let config = PipelineConfig {
+            clusters: Some(ClusterConfig {
+                tolerance: 5.0,
+                min_points: 100,
+            }),
+            ..PipelineConfig::default()
+        };
+        let mut record = Record::default();
+        let result = process_points(two_plants(), &config, &mut record);
+        assert!(matches!(result, Err(Error::Degenerate(_))));
+        assert!(record.plants.is_empty());
+    }
+}
@@ -0,0 +1,124 @@
+use crate::convex_hull_2d::{convex_hull, convex_hull_area};
+use crate::convex_hull_3d;
+use crate::convex_hull_3d::{convex_hull_surface_area, convex_hull_volume};
+use crate::error::Result;
+use crate::geometry::{Point, Triangle};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+// phenotyping traits of a single plant, computed on the points after soil removal.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct PlantTraits {
+    pub point_count: usize,
+    // area of the 2D hull (x-y plane), the convex hull or the concave hull with concave_k
+    pub canopy_area: f64,
+    // surface area of the 3D convex hull
+    pub hull_surface_area: f64,
+    // volume of the 3D convex hull
+    pub hull_volume: f64,
+    // volume of the alpha shape, only when it is computed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alpha_volume: Option<f64>,
+    // extent of the plant points along z, from the lowest to the highest point
+    pub max_height: f64,
+}
+
+impl PlantTraits {
+    // compute the traits from the plant points and the hulls that are already computed on them.
+    pub fn new(points: &[Point], hull_2d: &[Point], hull_3d: &[Triangle]) -> Self {
+        PlantTraits {
+            point_count: points.len(),
+            canopy_area: convex_hull_area(hull_2d),
+            hull_surface_area: convex_hull_surface_area(hull_3d),
+            hull_volume: convex_hull_volume(hull_3d),
+            alpha_volume: None,
+            max_height: max_height(points),
+        }
+    }
+
+    // compute both hulls and the traits from the plant points.
+    pub fn compute(points: &[Point]) -> Result<Self> {
+        let hull_2d = convex_hull(points.to_vec())?;
+        let hull_3d = convex_hull_3d::convex_hull(points.to_vec())?;
+        Ok(PlantTraits::new(points, &hull_2d, &hull_3d))
+    }
+}
+
+impl Display for PlantTraits {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "point count\t{}", self.point_count)?;
+        writeln!(f, "canopy area\t{}", self.canopy_area)?;
+        writeln!(f, "hull surface area\t{}", self.hull_surface_area)?;
+        writeln!(f, "hull volume\t{}", self.hull_volume)?;
+        if let Some(volume) = self.alpha_volume {
+            writeln!(f, "alpha shape volume\t{}", volume)?;
+        }
+        write!(f, "max height\t{}", self.max_height)
+    }
+}
+
+fn max_height(points: &[Point]) -> f64 {
+    if points.is_empty() {
+        return 0.0;
+    }
+    let mut min_z = f64::MAX;
+    let mut max_z = f64::MIN;
+    for pt in points {
+        if pt.z < min_z {
+            min_z = pt.z
+        }
+        if pt.z > max_z {
+            max_z = pt.z
+        }
+    }
+    max_z - min_z
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PlantTraits;
+    use crate::geometry::Point;
+
+    const TOLERANCE: f64 = 0.000001;
+
+    #[test]
+    fn test_plant_traits_octahedron() {
+        // octahedron centered at (0, 0, 3), stretched along x and y
+        let mut data = vec![
+            Point::new(2.0, 0.0, 3.0),
+            Point::new(-2.0, 0.0, 3.0),
+            Point::new(0.0, 3.0, 3.0),
+            Point::new(0.0, -3.0, 3.0),
+            Point::new(0.0, 0.0, 4.0),
+            Point::new(0.0, 0.0, 2.0),
+        ];
+        // points inside of the octahedron
+        data.push(Point::new(0.5, 0.5, 3.2));
+        data.push(Point::new(-0.3, 1.0, 2.9));
+        data.push(Point::new(0.1, -0.7, 2.5));
+
+        let traits = PlantTraits::compute(&data).unwrap();
+        assert_eq!(traits.point_count, 9);
+        // rhombus with diagonals 4 and 6
+        assert!((traits.canopy_area - 12.0).abs() < TOLERANCE, "{}", traits);
+        assert!((traits.max_height - 2.0).abs() < TOLERANCE, "{}", traits);
+        // 2 pyramids with the rhombus as base and height 1
+        assert!((traits.hull_volume - 8.0).abs() < TOLERANCE, "{}", traits);
+        // 8 triangles with sides sqrt(5), sqrt(10), sqrt(13)
+        let face_area = (4.0f64 * 9.0 + 4.0 * 1.0 + 9.0 * 1.0).sqrt() / 2.0;
+        assert!(
+            (traits.hull_surface_area - 8.0 * face_area).abs() < TOLERANCE,
+            "{}",
+            traits
+        );
+    }
+
+    #[test]
+    fn test_plant_traits_empty() {
+        let traits = PlantTraits::compute(&[]).unwrap();
+        assert_eq!(traits.point_count, 0);
+        assert_eq!(traits.canopy_area, 0.0);
+        assert_eq!(traits.hull_volume, 0.0);
+        assert_eq!(traits.max_height, 0.0);
+    }
+}
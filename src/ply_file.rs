@@ -1,13 +1,18 @@
 extern crate ply_rs;
 
 use self::ply_rs::ply::{ElementDef, Property, PropertyDef, PropertyType, ScalarType};
-use crate::geometry::Point;
+use crate::convex_hull_3d::HullMesh;
+use crate::error::{Error, Result};
+use crate::geometry::{Color, Point, Vec3D};
 use ply_rs::ply::DefaultElement;
 use ply_rs::ply::Ply;
-use std::any::Any;
+use std::collections::BTreeMap;
 
-pub fn read_ply_file(path: String) -> Ply<DefaultElement> {
-    let mut f = std::fs::File::open(path).unwrap();
+pub fn read_ply_file(path: String) -> Result<Ply<DefaultElement>> {
+    let mut f = match std::fs::File::open(&path) {
+        Ok(val) => val,
+        Err(err) => return Err(Error::Io(path, err)),
+    };
 
     // create a parser
     let p = ply_rs::parser::Parser::<ply_rs::ply::DefaultElement>::new();
@@ -15,112 +20,251 @@ pub fn read_ply_file(path: String) -> Ply<DefaultElement> {
     // use the parser: read the entire file
     let ply = p.read_ply(&mut f);
     match ply {
-        Ok(val) => val,
-        Err(_) => {
-            panic!()
-        }
+        Ok(val) => Ok(val),
+        Err(err) => Err(Error::Parse(path, err)),
     }
 }
 
-pub fn ply_to_pts(ply: Ply<DefaultElement>) -> Result<Vec<Point>, String> {
-    //debug_print(&ply);
+// point read from a PLY vertex, with the optional per-vertex properties.
+#[derive(Clone, Debug)]
+pub struct PlyPoint {
+    pub point: Point,
+    // red, green, blue
+    pub color: Option<Color>,
+    // nx, ny, nz
+    pub normal: Option<Vec3D>,
+    pub intensity: Option<f64>,
+    // any other scalar property of the vertex, by name
+    pub properties: BTreeMap<String, f64>,
+}
 
+pub fn ply_to_pts(ply: Ply<DefaultElement>) -> Result<Vec<Point>> {
     let vertices = match ply.payload.get("vertex") {
-        None => {
-            panic!()
-        }
+        None => return Err(Error::MissingElement(String::from("vertex"))),
         Some(val) => val,
     };
     let mut result = vec![];
     for vertex in vertices {
-        let point = match vertex_to_point(vertex) {
-            Ok(val) => val,
-            Err(err) => return Err(err),
-        };
+        let point = vertex_to_point(vertex)?;
         result.push(point);
     }
-    return Ok(result);
+    Ok(result)
 }
 
-#[allow(dead_code)]
-fn debug_print(ply: &Ply<DefaultElement>) {
-    println!("Ply header: {:#?}", ply.header);
-    let v1 = ply.payload.get("vertex").unwrap().get(0).unwrap().type_id();
-    println!("{:?}", v1);
-    let v2 = ply
-        .payload
-        .get("vertex")
-        .unwrap()
-        .get(0)
-        .unwrap()
-        .get("x")
-        .unwrap();
-    println!("{:?}", v2);
-    //println!("Ply data: {:?}", ply.payload);
+pub fn ply_to_ply_points(ply: Ply<DefaultElement>) -> Result<Vec<PlyPoint>> {
+    let vertices = match ply.payload.get("vertex") {
+        None => return Err(Error::MissingElement(String::from("vertex"))),
+        Some(val) => val,
+    };
+    let mut result = vec![];
+    for vertex in vertices {
+        let point = vertex_to_ply_point(vertex)?;
+        result.push(point);
+    }
+    Ok(result)
 }
 
-fn vertex_to_point(vertex: &DefaultElement) -> Result<Point, String> {
-    let x_property = match vertex.get("x") {
+fn vertex_to_point(vertex: &DefaultElement) -> Result<Point> {
+    let x = match scalar_property(vertex, "x")? {
         None => {
-            return Err(String::from("vertex missing x"));
+            return Err(Error::InvalidProperty(String::from("vertex missing x")));
         }
         Some(val) => val,
     };
-    let x = match x_property {
-        Property::Double(val) => val,
-        _ => {
-            return Err(String::from("vertex x coordinate is not double"));
-        }
-    };
-    let y_property = match vertex.get("y") {
+    let y = match scalar_property(vertex, "y")? {
         None => {
-            return Err(String::from("vertex missing y"));
+            return Err(Error::InvalidProperty(String::from("vertex missing y")));
         }
         Some(val) => val,
     };
-    let y = match y_property {
-        Property::Double(val) => val,
-        _ => {
-            return Err(String::from("vertex y coordinate is not double"));
-        }
-    };
-    let z_property = match vertex.get("z") {
+    let z = match scalar_property(vertex, "z")? {
         None => {
-            return Err(String::from("vertex missing z"));
+            return Err(Error::InvalidProperty(String::from("vertex missing z")));
         }
         Some(val) => val,
     };
-    let z = match z_property {
-        Property::Double(val) => val,
-        _ => {
-            return Err(String::from("vertex z coordinate is not double"));
+    if !(x.is_finite() && y.is_finite() && z.is_finite()) {
+        return Err(Error::InvalidProperty(format!(
+            "vertex ({}, {}, {}) is not finite",
+            x, y, z
+        )));
+    }
+    Ok(Point { x, y, z })
+}
+
+const COLOR_NAMES: [[&str; 3]; 2] = [
+    ["red", "green", "blue"],
+    ["diffuse_red", "diffuse_green", "diffuse_blue"],
+];
+const NORMAL_NAMES: [&str; 3] = ["nx", "ny", "nz"];
+const INTENSITY_NAMES: [&str; 2] = ["intensity", "scalar_intensity"];
+
+fn vertex_to_ply_point(vertex: &DefaultElement) -> Result<PlyPoint> {
+    let point = vertex_to_point(vertex)?;
+
+    let mut color = None;
+    for names in COLOR_NAMES.iter() {
+        if let (Some(red), Some(green), Some(blue)) = (
+            vertex.get(names[0]),
+            vertex.get(names[1]),
+            vertex.get(names[2]),
+        ) {
+            color = Some(Color::new(
+                color_channel(red)?,
+                color_channel(green)?,
+                color_channel(blue)?,
+            ));
+            break;
         }
+    }
+
+    let normal = match (
+        scalar_property(vertex, NORMAL_NAMES[0])?,
+        scalar_property(vertex, NORMAL_NAMES[1])?,
+        scalar_property(vertex, NORMAL_NAMES[2])?,
+    ) {
+        (Some(x), Some(y), Some(z)) => Some(Vec3D::new(x, y, z)),
+        _ => None,
     };
-    Ok(Point {
-        x: x.clone(),
-        y: y.clone(),
-        z: z.clone(),
+
+    let mut intensity = None;
+    for name in INTENSITY_NAMES.iter() {
+        if let Some(val) = scalar_property(vertex, name)? {
+            intensity = Some(val);
+            break;
+        }
+    }
+
+    let mut properties = BTreeMap::new();
+    for (name, property) in vertex.iter() {
+        let known = ["x", "y", "z"].contains(&name.as_str())
+            || COLOR_NAMES
+                .iter()
+                .any(|names| names.contains(&name.as_str()))
+            || NORMAL_NAMES.contains(&name.as_str())
+            || INTENSITY_NAMES.contains(&name.as_str());
+        if known {
+            continue;
+        }
+        // list properties are not passed through
+        if let Some(val) = scalar_to_f64(property) {
+            properties.insert(name.clone(), val);
+        }
+    }
+
+    Ok(PlyPoint {
+        point,
+        color,
+        normal,
+        intensity,
+        properties,
     })
 }
 
-pub fn write_to_ply_file(path: String, data: &Vec<Point>) {
-    let mut f = std::fs::File::create(path).unwrap();
+// value of a scalar property of any type, None if the vertex does not have the property.
+fn scalar_property(vertex: &DefaultElement, name: &str) -> Result<Option<f64>> {
+    match vertex.get(name) {
+        None => Ok(None),
+        Some(property) => match scalar_to_f64(property) {
+            None => Err(Error::InvalidProperty(format!(
+                "vertex {} is not a scalar",
+                name
+            ))),
+            Some(val) => Ok(Some(val)),
+        },
+    }
+}
+
+fn scalar_to_f64(property: &Property) -> Option<f64> {
+    match property {
+        Property::Char(val) => Some(*val as f64),
+        Property::UChar(val) => Some(*val as f64),
+        Property::Short(val) => Some(*val as f64),
+        Property::UShort(val) => Some(*val as f64),
+        Property::Int(val) => Some(*val as f64),
+        Property::UInt(val) => Some(*val as f64),
+        Property::Float(val) => Some(*val as f64),
+        Property::Double(val) => Some(*val),
+        _ => None,
+    }
+}
+
+// color channel in 0-255.
+// uchar is used as is, ushort is scaled down, float and double are expected to be in 0-1.
+fn color_channel(property: &Property) -> Result<u8> {
+    let val = match property {
+        Property::UChar(val) => return Ok(*val),
+        Property::UShort(val) => *val as f64 / 257.0,
+        Property::Float(val) => *val as f64 * 255.0,
+        Property::Double(val) => *val * 255.0,
+        _ => match scalar_to_f64(property) {
+            None => {
+                return Err(Error::InvalidProperty(String::from(
+                    "vertex color is not a scalar",
+                )))
+            }
+            Some(val) => val,
+        },
+    };
+    Ok(val.round().clamp(0.0, 255.0) as u8)
+}
 
+pub fn write_to_ply_file(path: String, data: &[Point]) -> Result<()> {
     let mut ply = pts_to_ply(data);
+    write_ply(path, &mut ply)
+}
 
-    // set up a writer
-    let w = ply_rs::writer::Writer::new();
-    let written = match w.write_ply(&mut f, &mut ply) {
+// write the hull as a surface, with a vertex element and a face element (vertex_indices).
+pub fn write_mesh_to_ply_file(path: String, mesh: &HullMesh) -> Result<()> {
+    let faces: Vec<Vec<u32>> = mesh.faces.iter().map(|face| face.to_vec()).collect();
+    write_faces_to_ply_file(path, &mesh.vertices, &faces)
+}
+
+// same as write_mesh_to_ply_file, for polygons of any number of vertices.
+pub fn write_faces_to_ply_file(path: String, vertices: &[Point], faces: &[Vec<u32>]) -> Result<()> {
+    let mut ply = pts_to_ply(vertices);
+    let face_header = String::from("face");
+    let vertex_indices_str = String::from("vertex_indices");
+
+    let mut elem_def = ElementDef {
+        name: face_header.clone(),
+        count: faces.len(),
+        properties: Default::default(),
+    };
+    let p_def = PropertyDef {
+        name: vertex_indices_str.clone(),
+        data_type: PropertyType::List(ScalarType::UChar, ScalarType::UInt),
+    };
+    elem_def
+        .properties
+        .insert(vertex_indices_str.clone(), p_def);
+    ply.header.elements.insert(face_header.clone(), elem_def);
+
+    let mut elements: Vec<DefaultElement> = vec![];
+    for face in faces {
+        let mut elem = DefaultElement::new();
+        elem.insert(vertex_indices_str.clone(), Property::ListUInt(face.clone()));
+        elements.push(elem);
+    }
+    ply.payload.insert(face_header, elements);
+    write_ply(path, &mut ply)
+}
+
+fn write_ply(path: String, ply: &mut Ply<DefaultElement>) -> Result<()> {
+    let mut f = match std::fs::File::create(&path) {
         Ok(val) => val,
-        Err(err) => {
-            eprintln!("{}", err);
-            panic!();
-        }
+        Err(err) => return Err(Error::Io(path, err)),
     };
-    println!("{} bytes written", written);
+
+    // set up a writer
+    let w = ply_rs::writer::Writer::new();
+    match w.write_ply(&mut f, ply) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(Error::Io(path, err)),
+    }
 }
 
-fn pts_to_ply(data: &Vec<Point>) -> Ply<DefaultElement> {
+fn pts_to_ply(data: &[Point]) -> Ply<DefaultElement> {
     let mut ply = Ply::<DefaultElement>::new();
     let x_str = String::from("x");
     let y_str = String::from("y");
@@ -173,3 +317,109 @@ fn ply_header(data_len: usize) -> ElementDef {
     elem_def.properties.insert(z_str, p_def_z);
     elem_def
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ply_to_ply_points, ply_to_pts, read_ply_file, write_faces_to_ply_file};
+    use crate::error::Error;
+    use crate::geometry::{Color, Point};
+    use ply_rs::ply::{DefaultElement, Ply, Property};
+
+    fn vertex_ply(vertices: Vec<DefaultElement>) -> Ply<DefaultElement> {
+        let mut ply = Ply::<DefaultElement>::new();
+        ply.payload.insert(String::from("vertex"), vertices);
+        ply
+    }
+
+    #[test]
+    fn test_read_scalar_types() {
+        let mut v1 = DefaultElement::new();
+        v1.insert(String::from("x"), Property::Float(1.5));
+        v1.insert(String::from("y"), Property::Int(-2));
+        v1.insert(String::from("z"), Property::UShort(3));
+        let mut v2 = DefaultElement::new();
+        v2.insert(String::from("x"), Property::Char(-1));
+        v2.insert(String::from("y"), Property::UInt(7));
+        v2.insert(String::from("z"), Property::Double(0.25));
+
+        let pts = ply_to_pts(vertex_ply(vec![v1, v2])).unwrap();
+        assert_eq!(pts.len(), 2);
+        assert_eq!((pts[0].x, pts[0].y, pts[0].z), (1.5, -2.0, 3.0));
+        assert_eq!((pts[1].x, pts[1].y, pts[1].z), (-1.0, 7.0, 0.25));
+    }
+
+    #[test]
+    fn test_read_optional_properties() {
+        let mut v = DefaultElement::new();
+        v.insert(String::from("x"), Property::Float(1.0));
+        v.insert(String::from("y"), Property::Float(2.0));
+        v.insert(String::from("z"), Property::Float(3.0));
+        v.insert(String::from("red"), Property::UChar(10));
+        v.insert(String::from("green"), Property::Float(1.0));
+        v.insert(String::from("blue"), Property::UShort(65535));
+        v.insert(String::from("nx"), Property::Float(0.0));
+        v.insert(String::from("ny"), Property::Float(0.0));
+        v.insert(String::from("nz"), Property::Float(1.0));
+        v.insert(String::from("intensity"), Property::UShort(500));
+        v.insert(String::from("label"), Property::Int(4));
+
+        let pts = ply_to_ply_points(vertex_ply(vec![v])).unwrap();
+        let pt = &pts[0];
+        assert_eq!((pt.point.x, pt.point.y, pt.point.z), (1.0, 2.0, 3.0));
+        assert_eq!(pt.color, Some(Color::new(10, 255, 255)));
+        let normal = pt.normal.unwrap();
+        assert_eq!((normal.x, normal.y, normal.z), (0.0, 0.0, 1.0));
+        assert_eq!(pt.intensity, Some(500.0));
+        assert_eq!(pt.properties.len(), 1);
+        assert_eq!(pt.properties.get("label"), Some(&4.0));
+    }
+
+    #[test]
+    fn test_write_faces() {
+        let path = std::env::temp_dir().join(format!("faces-{}.ply", std::process::id()));
+        let path = path.display().to_string();
+        let vertices: Vec<Point> = (0..5)
+            .map(|i| Point::new(i as f64, (i * i) as f64, 1.0))
+            .collect();
+        let faces = vec![vec![0, 1, 2], vec![0, 2, 3, 4]];
+        write_faces_to_ply_file(path.clone(), &vertices, &faces).unwrap();
+        let ply = read_ply_file(path.clone()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(ply_to_pts(ply.clone()).unwrap().len(), 5);
+        let read: Vec<Vec<u32>> = ply.payload["face"]
+            .iter()
+            .map(|face| match &face["vertex_indices"] {
+                Property::ListUInt(indices) => indices.clone(),
+                _ => panic!("expected a list of indices"),
+            })
+            .collect();
+        assert_eq!(read, faces);
+    }
+
+    #[test]
+    fn test_read_errors() {
+        let path = String::from("data/does-not-exist.ply");
+        assert!(matches!(read_ply_file(path), Err(Error::Io(_, _))));
+        assert!(matches!(
+            ply_to_pts(Ply::<DefaultElement>::new()),
+            Err(Error::MissingElement(_))
+        ));
+        let mut v = DefaultElement::new();
+        v.insert(String::from("x"), Property::Float(f32::NAN));
+        v.insert(String::from("y"), Property::Float(2.0));
+        v.insert(String::from("z"), Property::Float(3.0));
+        assert!(matches!(
+            ply_to_pts(vertex_ply(vec![v])),
+            Err(Error::InvalidProperty(_))
+        ));
+    }
+
+    #[test]
+    fn test_read_list_coordinate() {
+        let mut v = DefaultElement::new();
+        v.insert(String::from("x"), Property::ListFloat(vec![1.0]));
+        v.insert(String::from("y"), Property::Float(2.0));
+        v.insert(String::from("z"), Property::Float(3.0));
+        assert!(ply_to_pts(vertex_ply(vec![v])).is_err());
+    }
+}
@@ -0,0 +1,601 @@
+use crate::geometry::Point;
+
+// Robust geometric predicates, after Shewchuk,
+// "Adaptive Precision Floating-Point Arithmetic and Fast Robust Geometric Predicates".
+// https://www.cs.cmu.edu/~quake/robust.html
+//
+// the determinant is first evaluated with plain f64 arithmetic, if the result is too small to
+// trust its sign (given the error bound), it is evaluated again with exact expansion arithmetic.
+// Only the sign of the returned value is exact, the magnitude is an approximation.
+
+// half of the machine epsilon, the relative rounding error of a f64 operation.
+const EPSILON: f64 = f64::EPSILON / 2.0;
+const CCW_ERR_BOUND_A: f64 = (3.0 + 16.0 * EPSILON) * EPSILON;
+const O3D_ERR_BOUND_A: f64 = (7.0 + 56.0 * EPSILON) * EPSILON;
+const ICC_ERR_BOUND_A: f64 = (10.0 + 96.0 * EPSILON) * EPSILON;
+const ISP_ERR_BOUND_A: f64 = (16.0 + 224.0 * EPSILON) * EPSILON;
+
+// twice the signed area of the triangle (pa, pb, pc) in the x-y plane.
+// positive if the points are in counter-clockwise order, negative if clockwise, zero if collinear.
+// same as pa->pb X pa->pc
+pub fn orient2d(pa: &Point, pb: &Point, pc: &Point) -> f64 {
+    let det_left = (pa.x - pc.x) * (pb.y - pc.y);
+    let det_right = (pa.y - pc.y) * (pb.x - pc.x);
+    let det = det_left - det_right;
+
+    let det_sum = if det_left > 0.0 {
+        if det_right <= 0.0 {
+            return det;
+        }
+        det_left + det_right
+    } else if det_left < 0.0 {
+        if det_right >= 0.0 {
+            return det;
+        }
+        -det_left - det_right
+    } else {
+        return det;
+    };
+    let err_bound = CCW_ERR_BOUND_A * det_sum;
+    if det >= err_bound || -det >= err_bound {
+        return det;
+    }
+    orient2d_exact(pa, pb, pc)
+}
+
+// six times the signed volume of the tetrahedron (pa, pb, pc, pd).
+// positive if pd is below the plane of (pa, pb, pc), where pa, pb, pc appear in counter-clockwise
+// order seen from above the plane (the side pa->pb X pa->pc points to).
+// negative if pd is above the plane, zero if the 4 points are coplanar.
+pub fn orient3d(pa: &Point, pb: &Point, pc: &Point, pd: &Point) -> f64 {
+    let adx = pa.x - pd.x;
+    let bdx = pb.x - pd.x;
+    let cdx = pc.x - pd.x;
+    let ady = pa.y - pd.y;
+    let bdy = pb.y - pd.y;
+    let cdy = pc.y - pd.y;
+    let adz = pa.z - pd.z;
+    let bdz = pb.z - pd.z;
+    let cdz = pc.z - pd.z;
+
+    let bdxcdy = bdx * cdy;
+    let cdxbdy = cdx * bdy;
+    let cdxady = cdx * ady;
+    let adxcdy = adx * cdy;
+    let adxbdy = adx * bdy;
+    let bdxady = bdx * ady;
+
+    let det = adz * (bdxcdy - cdxbdy) + bdz * (cdxady - adxcdy) + cdz * (adxbdy - bdxady);
+    let permanent = (bdxcdy.abs() + cdxbdy.abs()) * adz.abs()
+        + (cdxady.abs() + adxcdy.abs()) * bdz.abs()
+        + (adxbdy.abs() + bdxady.abs()) * cdz.abs();
+    let err_bound = O3D_ERR_BOUND_A * permanent;
+    if det > err_bound || -det > err_bound {
+        return det;
+    }
+    orient3d_exact(pa, pb, pc, pd)
+}
+
+// positive if pd is inside the circle through pa, pb, pc in the x-y plane, negative if outside,
+// zero if the 4 points are on the same circle. pa, pb, pc must be in counter-clockwise order, the
+// sign is reversed otherwise.
+pub fn incircle(pa: &Point, pb: &Point, pc: &Point, pd: &Point) -> f64 {
+    let adx = pa.x - pd.x;
+    let bdx = pb.x - pd.x;
+    let cdx = pc.x - pd.x;
+    let ady = pa.y - pd.y;
+    let bdy = pb.y - pd.y;
+    let cdy = pc.y - pd.y;
+
+    let bdxcdy = bdx * cdy;
+    let cdxbdy = cdx * bdy;
+    let alift = adx * adx + ady * ady;
+    let cdxady = cdx * ady;
+    let adxcdy = adx * cdy;
+    let blift = bdx * bdx + bdy * bdy;
+    let adxbdy = adx * bdy;
+    let bdxady = bdx * ady;
+    let clift = cdx * cdx + cdy * cdy;
+
+    let det = alift * (bdxcdy - cdxbdy) + blift * (cdxady - adxcdy) + clift * (adxbdy - bdxady);
+    let permanent = (bdxcdy.abs() + cdxbdy.abs()) * alift
+        + (cdxady.abs() + adxcdy.abs()) * blift
+        + (adxbdy.abs() + bdxady.abs()) * clift;
+    let err_bound = ICC_ERR_BOUND_A * permanent;
+    if det > err_bound || -det > err_bound {
+        return det;
+    }
+    incircle_exact(pa, pb, pc, pd)
+}
+
+// positive if pe is inside the sphere through pa, pb, pc, pd, negative if outside, zero if the 5
+// points are on the same sphere. pa, pb, pc, pd must be positively oriented
+// (orient3d(pa, pb, pc, pd) > 0), the sign is reversed otherwise.
+pub fn insphere(pa: &Point, pb: &Point, pc: &Point, pd: &Point, pe: &Point) -> f64 {
+    let aex = pa.x - pe.x;
+    let bex = pb.x - pe.x;
+    let cex = pc.x - pe.x;
+    let dex = pd.x - pe.x;
+    let aey = pa.y - pe.y;
+    let bey = pb.y - pe.y;
+    let cey = pc.y - pe.y;
+    let dey = pd.y - pe.y;
+    let aez = pa.z - pe.z;
+    let bez = pb.z - pe.z;
+    let cez = pc.z - pe.z;
+    let dez = pd.z - pe.z;
+
+    let aexbey = aex * bey;
+    let bexaey = bex * aey;
+    let bexcey = bex * cey;
+    let cexbey = cex * bey;
+    let cexdey = cex * dey;
+    let dexcey = dex * cey;
+    let dexaey = dex * aey;
+    let aexdey = aex * dey;
+    let aexcey = aex * cey;
+    let cexaey = cex * aey;
+    let bexdey = bex * dey;
+    let dexbey = dex * bey;
+    let ab = aexbey - bexaey;
+    let bc = bexcey - cexbey;
+    let cd = cexdey - dexcey;
+    let da = dexaey - aexdey;
+    let ac = aexcey - cexaey;
+    let bd = bexdey - dexbey;
+
+    let abc = aez * bc - bez * ac + cez * ab;
+    let bcd = bez * cd - cez * bd + dez * bc;
+    let cda = cez * da + dez * ac + aez * cd;
+    let dab = dez * ab + aez * bd + bez * da;
+
+    let alift = aex * aex + aey * aey + aez * aez;
+    let blift = bex * bex + bey * bey + bez * bez;
+    let clift = cex * cex + cey * cey + cez * cez;
+    let dlift = dex * dex + dey * dey + dez * dez;
+
+    let det = (dlift * abc - clift * dab) + (blift * cda - alift * bcd);
+
+    let (aez, bez, cez, dez) = (aez.abs(), bez.abs(), cez.abs(), dez.abs());
+    let ab = aexbey.abs() + bexaey.abs();
+    let bc = bexcey.abs() + cexbey.abs();
+    let cd = cexdey.abs() + dexcey.abs();
+    let da = dexaey.abs() + aexdey.abs();
+    let ac = aexcey.abs() + cexaey.abs();
+    let bd = bexdey.abs() + dexbey.abs();
+    let permanent = (cd * bez + bd * cez + bc * dez) * alift
+        + (da * cez + ac * dez + cd * aez) * blift
+        + (ab * dez + bd * aez + da * bez) * clift
+        + (bc * aez + ac * bez + ab * cez) * dlift;
+    let err_bound = ISP_ERR_BOUND_A * permanent;
+    if det > err_bound || -det > err_bound {
+        return det;
+    }
+    insphere_exact(pa, pb, pc, pd, pe)
+}
+
+// true if the 3 points are on a line, in 3D.
+pub fn collinear3d(pa: &Point, pb: &Point, pc: &Point) -> bool {
+    let yz = |pt: &Point| Point::new(pt.y, pt.z, 0.0);
+    let zx = |pt: &Point| Point::new(pt.z, pt.x, 0.0);
+    orient2d(pa, pb, pc) == 0.0
+        && orient2d(&yz(pa), &yz(pb), &yz(pc)) == 0.0
+        && orient2d(&zx(pa), &zx(pb), &zx(pc)) == 0.0
+}
+
+fn orient2d_exact(pa: &Point, pb: &Point, pc: &Point) -> f64 {
+    let acx = two_diff(pa.x, pc.x);
+    let bcy = two_diff(pb.y, pc.y);
+    let acy = two_diff(pa.y, pc.y);
+    let bcx = two_diff(pb.x, pc.x);
+    let det = expansion_diff(
+        &expansion_product(&acx, &bcy),
+        &expansion_product(&acy, &bcx),
+    );
+    estimate(&det)
+}
+
+fn orient3d_exact(pa: &Point, pb: &Point, pc: &Point, pd: &Point) -> f64 {
+    let adx = two_diff(pa.x, pd.x);
+    let bdx = two_diff(pb.x, pd.x);
+    let cdx = two_diff(pc.x, pd.x);
+    let ady = two_diff(pa.y, pd.y);
+    let bdy = two_diff(pb.y, pd.y);
+    let cdy = two_diff(pc.y, pd.y);
+    let adz = two_diff(pa.z, pd.z);
+    let bdz = two_diff(pb.z, pd.z);
+    let cdz = two_diff(pc.z, pd.z);
+
+    let bc = expansion_diff(
+        &expansion_product(&bdx, &cdy),
+        &expansion_product(&cdx, &bdy),
+    );
+    let ca = expansion_diff(
+        &expansion_product(&cdx, &ady),
+        &expansion_product(&adx, &cdy),
+    );
+    let ab = expansion_diff(
+        &expansion_product(&adx, &bdy),
+        &expansion_product(&bdx, &ady),
+    );
+    let det = expansion_sum(
+        &expansion_sum(&expansion_product(&adz, &bc), &expansion_product(&bdz, &ca)),
+        &expansion_product(&cdz, &ab),
+    );
+    estimate(&det)
+}
+
+fn incircle_exact(pa: &Point, pb: &Point, pc: &Point, pd: &Point) -> f64 {
+    let adx = two_diff(pa.x, pd.x);
+    let bdx = two_diff(pb.x, pd.x);
+    let cdx = two_diff(pc.x, pd.x);
+    let ady = two_diff(pa.y, pd.y);
+    let bdy = two_diff(pb.y, pd.y);
+    let cdy = two_diff(pc.y, pd.y);
+    let lift =
+        |x: &[f64], y: &[f64]| expansion_sum(&expansion_product(x, x), &expansion_product(y, y));
+
+    let bc = expansion_diff(
+        &expansion_product(&bdx, &cdy),
+        &expansion_product(&cdx, &bdy),
+    );
+    let ca = expansion_diff(
+        &expansion_product(&cdx, &ady),
+        &expansion_product(&adx, &cdy),
+    );
+    let ab = expansion_diff(
+        &expansion_product(&adx, &bdy),
+        &expansion_product(&bdx, &ady),
+    );
+    let det = expansion_sum(
+        &expansion_sum(
+            &expansion_product(&lift(&adx, &ady), &bc),
+            &expansion_product(&lift(&bdx, &bdy), &ca),
+        ),
+        &expansion_product(&lift(&cdx, &cdy), &ab),
+    );
+    estimate(&det)
+}
+
+fn insphere_exact(pa: &Point, pb: &Point, pc: &Point, pd: &Point, pe: &Point) -> f64 {
+    let diff = |pt: &Point| {
+        (
+            two_diff(pt.x, pe.x),
+            two_diff(pt.y, pe.y),
+            two_diff(pt.z, pe.z),
+        )
+    };
+    let (aex, aey, aez) = diff(pa);
+    let (bex, bey, bez) = diff(pb);
+    let (cex, cey, cez) = diff(pc);
+    let (dex, dey, dez) = diff(pd);
+    let cross = |x1: &[f64], y2: &[f64], x2: &[f64], y1: &[f64]| {
+        expansion_diff(&expansion_product(x1, y2), &expansion_product(x2, y1))
+    };
+    let ab = cross(&aex, &bey, &bex, &aey);
+    let bc = cross(&bex, &cey, &cex, &bey);
+    let cd = cross(&cex, &dey, &dex, &cey);
+    let da = cross(&dex, &aey, &aex, &dey);
+    let ac = cross(&aex, &cey, &cex, &aey);
+    let bd = cross(&bex, &dey, &dex, &bey);
+
+    let abc = expansion_sum(
+        &expansion_diff(&expansion_product(&aez, &bc), &expansion_product(&bez, &ac)),
+        &expansion_product(&cez, &ab),
+    );
+    let bcd = expansion_sum(
+        &expansion_diff(&expansion_product(&bez, &cd), &expansion_product(&cez, &bd)),
+        &expansion_product(&dez, &bc),
+    );
+    let cda = expansion_sum(
+        &expansion_sum(&expansion_product(&cez, &da), &expansion_product(&dez, &ac)),
+        &expansion_product(&aez, &cd),
+    );
+    let dab = expansion_sum(
+        &expansion_sum(&expansion_product(&dez, &ab), &expansion_product(&aez, &bd)),
+        &expansion_product(&bez, &da),
+    );
+    let lift = |x: &[f64], y: &[f64], z: &[f64]| {
+        expansion_sum(
+            &expansion_sum(&expansion_product(x, x), &expansion_product(y, y)),
+            &expansion_product(z, z),
+        )
+    };
+    let alift = lift(&aex, &aey, &aez);
+    let blift = lift(&bex, &bey, &bez);
+    let clift = lift(&cex, &cey, &cez);
+    let dlift = lift(&dex, &dey, &dez);
+
+    let det = expansion_sum(
+        &expansion_diff(
+            &expansion_product(&dlift, &abc),
+            &expansion_product(&clift, &dab),
+        ),
+        &expansion_diff(
+            &expansion_product(&blift, &cda),
+            &expansion_product(&alift, &bcd),
+        ),
+    );
+    estimate(&det)
+}
+
+// An expansion is a sum of f64 components, non-overlapping and sorted by increasing magnitude,
+// zero components are eliminated. The sign of the sum is the sign of the largest component.
+
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let x = a + b;
+    let b_virtual = x - a;
+    let a_virtual = x - b_virtual;
+    let b_roundoff = b - b_virtual;
+    let a_roundoff = a - a_virtual;
+    (x, a_roundoff + b_roundoff)
+}
+
+// requires |a| >= |b|
+fn fast_two_sum(a: f64, b: f64) -> (f64, f64) {
+    let x = a + b;
+    let b_virtual = x - a;
+    (x, b - b_virtual)
+}
+
+fn two_product(a: f64, b: f64) -> (f64, f64) {
+    let x = a * b;
+    // fused multiply-add gives the exact roundoff of the product
+    (x, a.mul_add(b, -x))
+}
+
+// a - b as an expansion
+fn two_diff(a: f64, b: f64) -> Vec<f64> {
+    let (x, y) = two_sum(a, -b);
+    zero_elim(vec![y, x])
+}
+
+fn zero_elim(e: Vec<f64>) -> Vec<f64> {
+    e.into_iter().filter(|c| *c != 0.0).collect()
+}
+
+// e + b
+fn grow_expansion(e: &[f64], b: f64) -> Vec<f64> {
+    let mut result = Vec::with_capacity(e.len() + 1);
+    let mut q = b;
+    for &component in e {
+        let (sum, roundoff) = two_sum(q, component);
+        if roundoff != 0.0 {
+            result.push(roundoff);
+        }
+        q = sum;
+    }
+    if q != 0.0 {
+        result.push(q);
+    }
+    result
+}
+
+// e + f
+fn expansion_sum(e: &[f64], f: &[f64]) -> Vec<f64> {
+    let mut result = e.to_vec();
+    for &component in f {
+        result = grow_expansion(&result, component);
+    }
+    result
+}
+
+// e - f
+fn expansion_diff(e: &[f64], f: &[f64]) -> Vec<f64> {
+    let negated: Vec<f64> = f.iter().map(|c| -c).collect();
+    expansion_sum(e, &negated)
+}
+
+// e * b
+fn scale_expansion(e: &[f64], b: f64) -> Vec<f64> {
+    let mut result = Vec::with_capacity(e.len() * 2);
+    if e.is_empty() {
+        return result;
+    }
+    let (mut q, roundoff) = two_product(e[0], b);
+    if roundoff != 0.0 {
+        result.push(roundoff);
+    }
+    for &component in &e[1..] {
+        let (product, product_roundoff) = two_product(component, b);
+        let (sum, roundoff) = two_sum(q, product_roundoff);
+        if roundoff != 0.0 {
+            result.push(roundoff);
+        }
+        let (new_q, roundoff) = fast_two_sum(product, sum);
+        if roundoff != 0.0 {
+            result.push(roundoff);
+        }
+        q = new_q;
+    }
+    if q != 0.0 {
+        result.push(q);
+    }
+    result
+}
+
+// e * f
+fn expansion_product(e: &[f64], f: &[f64]) -> Vec<f64> {
+    let mut result = vec![];
+    for &component in f {
+        result = expansion_sum(&result, &scale_expansion(e, component));
+    }
+    result
+}
+
+fn estimate(e: &[f64]) -> f64 {
+    e.iter().sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{collinear3d, incircle, insphere, orient2d, orient3d};
+    use crate::geometry::Point;
+
+    // 2^-53, the spacing of f64 around 0.5
+    const ULP: f64 = f64::EPSILON / 2.0;
+
+    fn sign(val: f64) -> i32 {
+        if val > 0.0 {
+            1
+        } else if val < 0.0 {
+            -1
+        } else {
+            0
+        }
+    }
+
+    #[test]
+    fn test_orient2d_simple() {
+        let a = Point::new2d(0.0, 0.0);
+        let b = Point::new2d(1.0, 0.0);
+        let c = Point::new2d(0.0, 1.0);
+        assert!(orient2d(&a, &b, &c) > 0.0);
+        assert!(orient2d(&a, &c, &b) < 0.0);
+        assert_eq!(orient2d(&a, &b, &Point::new2d(5.0, 0.0)), 0.0);
+    }
+
+    #[test]
+    fn test_orient2d_near_collinear() {
+        // points close to the line y = x, the naive cross product gets the sign wrong on many of them
+        let q = Point::new2d(12.0, 12.0);
+        let r = Point::new2d(24.0, 24.0);
+        for i in 0..64 {
+            for j in 0..64 {
+                let p = Point::new2d(0.5 + i as f64 * ULP, 0.5 + j as f64 * ULP);
+                // exact value is 12 * (j - i) * ULP
+                let expected = sign(j as f64 - i as f64);
+                assert_eq!(sign(orient2d(&p, &q, &r)), expected, "i: {}, j: {}", i, j);
+            }
+        }
+    }
+
+    #[test]
+    fn test_orient3d_simple() {
+        let a = Point::new(0.0, 0.0, 0.0);
+        let b = Point::new(1.0, 0.0, 0.0);
+        let c = Point::new(0.0, 1.0, 0.0);
+        assert!(orient3d(&a, &b, &c, &Point::new(0.0, 0.0, -1.0)) > 0.0);
+        assert!(orient3d(&a, &b, &c, &Point::new(0.0, 0.0, 1.0)) < 0.0);
+        assert_eq!(orient3d(&a, &b, &c, &Point::new(3.0, 7.0, 0.0)), 0.0);
+    }
+
+    #[test]
+    fn test_orient3d_near_coplanar() {
+        // a, b, c are on the plane z = x, d is close to the plane
+        let a = Point::new(12.0, 0.0, 12.0);
+        let b = Point::new(24.0, 5.0, 24.0);
+        let c = Point::new(0.0, 7.0, 0.0);
+        let above = orient3d(&a, &b, &c, &Point::new(0.5, 0.3, 10.0));
+        for i in 0..64 {
+            for j in 0..64 {
+                let d = Point::new(0.5 + i as f64 * ULP, 0.3, 0.5 + j as f64 * ULP);
+                // d is on the same side as the point above the plane when z > x
+                let expected = sign(above) * sign(j as f64 - i as f64);
+                assert_eq!(
+                    sign(orient3d(&a, &b, &c, &d)),
+                    expected,
+                    "i: {}, j: {}",
+                    i,
+                    j
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_orient3d_large_coordinates() {
+        // scanner coordinates are often large offsets, e.g. UTM
+        let offset = 409_000.123_456;
+        let a = Point::new(offset, offset + 1.0, 10.0);
+        let b = Point::new(offset + 1.0, offset + 1.0, 10.0);
+        let c = Point::new(offset, offset + 2.0, 10.0);
+        assert_eq!(
+            orient3d(&a, &b, &c, &Point::new(offset + 0.7, offset + 0.1, 10.0)),
+            0.0
+        );
+        assert!(orient3d(&a, &b, &c, &Point::new(offset, offset, 10.0 + 1e-12)) < 0.0);
+        assert!(orient3d(&a, &b, &c, &Point::new(offset, offset, 10.0 - 1e-12)) > 0.0);
+    }
+
+    #[test]
+    fn test_incircle() {
+        let a = Point::new2d(1.0, 0.0);
+        let b = Point::new2d(0.0, 1.0);
+        let c = Point::new2d(-1.0, 0.0);
+        assert!(incircle(&a, &b, &c, &Point::new2d(0.2, -0.3)) > 0.0);
+        assert!(incircle(&a, &b, &c, &Point::new2d(1.0, 1.0)) < 0.0);
+        assert_eq!(incircle(&a, &b, &c, &Point::new2d(0.0, -1.0)), 0.0);
+        assert!(incircle(&b, &a, &c, &Point::new2d(0.2, -0.3)) < 0.0);
+
+        // the corners of a square with a large offset, the last one moved by a few ulps
+        let offset = 409_000.5;
+        let ulp = offset * f64::EPSILON;
+        let a = Point::new2d(offset, offset);
+        let b = Point::new2d(offset + 1.0, offset);
+        let c = Point::new2d(offset + 1.0, offset + 1.0);
+        for i in -8..=8 {
+            let d = Point::new2d(offset + i as f64 * ulp, offset + 1.0);
+            assert_eq!(sign(incircle(&a, &b, &c, &d)), sign(i as f64), "i: {}", i);
+        }
+    }
+
+    #[test]
+    fn test_insphere() {
+        // the unit sphere at the origin through 4 positively oriented points
+        let a = Point::new(1.0, 0.0, 0.0);
+        let b = Point::new(0.0, 1.0, 0.0);
+        let c = Point::new(-1.0, 0.0, 0.0);
+        let d = Point::new(0.0, 0.0, -1.0);
+        assert!(orient3d(&a, &b, &c, &d) > 0.0);
+        assert!(insphere(&a, &b, &c, &d, &Point::new(0.1, 0.2, 0.3)) > 0.0);
+        assert!(insphere(&a, &b, &c, &d, &Point::new(1.0, 1.0, 0.0)) < 0.0);
+        assert_eq!(insphere(&a, &b, &c, &d, &Point::new(0.0, -1.0, 0.0)), 0.0);
+        assert_eq!(insphere(&a, &b, &c, &d, &Point::new(0.0, 0.0, 1.0)), 0.0);
+        // negative orientation reverses the sign
+        assert!(insphere(&b, &a, &c, &d, &Point::new(0.1, 0.2, 0.3)) < 0.0);
+    }
+
+    #[test]
+    fn test_insphere_near_cospherical() {
+        // the corners of a cube are on a sphere, move the last one along x by a few ulps
+        let offset = 409_000.5;
+        let p = |x: f64, y: f64, z: f64| Point::new(offset + x, offset + y, offset + z);
+        let (a, b, c, d) = (
+            p(0.0, 0.0, 0.0),
+            p(1.0, 0.0, 0.0),
+            p(0.0, 1.0, 0.0),
+            p(0.0, 0.0, -1.0),
+        );
+        let (a, b) = if orient3d(&a, &b, &c, &d) > 0.0 {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        // (1, 1, -1) is on the sphere, inside when moved toward the center (x < 1)
+        let ulp = offset * f64::EPSILON;
+        for i in -8..=8 {
+            let e = Point::new(offset + 1.0 + i as f64 * ulp, offset + 1.0, offset - 1.0);
+            assert_eq!(
+                sign(insphere(&a, &b, &c, &d, &e)),
+                -sign(i as f64),
+                "i: {}",
+                i
+            );
+        }
+    }
+
+    #[test]
+    fn test_collinear3d() {
+        assert!(collinear3d(
+            &Point::new(1.0, 1.0, 1.0),
+            &Point::new(2.0, 2.0, 2.0),
+            &Point::new(4.0, 4.0, 4.0)
+        ));
+        assert!(!collinear3d(
+            &Point::new(1.0, 1.0, 1.0),
+            &Point::new(2.0, 2.0, 2.0),
+            &Point::new(4.0, 4.0, 4.0 + 1e-12)
+        ));
+    }
+}
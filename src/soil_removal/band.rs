@@ -0,0 +1,306 @@
+use crate::error::{Error, Result};
+use crate::geometry::{sort_points_by_x, Point};
+
+// edge based segmentation: the cloud is split into bands along y, and each band is walked along
+// x. the plant starts where the ground rises steeply and ends where it has come back down.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BandConfig {
+    // number of bands the y range is split into
+    pub band_count: usize,
+    // number of points the slope is measured over
+    pub window: usize,
+    // the plant starts where dz / dx is above this
+    pub rise_slope: f64,
+    // the plant ends after dz / dx went below -fall_slope and the band is back near the soil
+    pub fall_slope: f64,
+}
+
+impl Default for BandConfig {
+    fn default() -> Self {
+        BandConfig {
+            band_count: 100,
+            window: 5,
+            rise_slope: 1.0,
+            fall_slope: 1.0,
+        }
+    }
+}
+
+impl BandConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.band_count == 0 {
+            return Err(Error::InvalidArgument(String::from(
+                "band count must be at least 1",
+            )));
+        }
+        if self.window < 2 {
+            return Err(Error::InvalidArgument(String::from(
+                "the slope window needs at least 2 points",
+            )));
+        }
+        for slope in [self.rise_slope, self.fall_slope] {
+            if !(slope > 0.0 && slope.is_finite()) {
+                return Err(Error::InvalidArgument(format!(
+                    "slope threshold {} must be positive",
+                    slope
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+// remove the soil with the band segmenter, the result is sorted by x.
+pub fn remove_soil(mut points: Vec<Point>, config: &BandConfig) -> Result<Vec<Point>> {
+    config.validate()?;
+    if points.is_empty() {
+        return Ok(points);
+    }
+    // min_y, max_y
+    let mut min_y: f64 = f64::MAX;
+    let mut max_y: f64 = f64::MIN;
+    for pt in &points {
+        if pt.y < min_y {
+            min_y = pt.y
+        }
+        if pt.y > max_y {
+            max_y = pt.y
+        }
+    }
+    // sort_by x
+    sort_points_by_x(&mut points);
+
+    let band_count = config.band_count;
+    let mut all_bands: Vec<Band> = (0..band_count).map(|_| Band::new(config)).collect();
+    let band_width: f64 = (max_y - min_y) / band_count as f64;
+
+    let mut is_plant = vec![false; points.len()];
+    for (i, pt) in points.iter().enumerate() {
+        // check which band the pt fall into, the highest y is in the last band
+        let band_index = ((pt.y - min_y) / band_width) as usize;
+        let band = &mut all_bands[band_index.min(band_count - 1)];
+        band.process(i, pt, &mut is_plant);
+    }
+
+    let mut plant = is_plant.iter();
+    points.retain(|_| *plant.next().unwrap());
+    Ok(points)
+}
+
+struct Band {
+    config: BandConfig,
+    // the last points of the band, (index in the sorted points, point)
+    previous_pts: Vec<(usize, Point)>,
+    in_plant: bool,
+    // the slope went below -fall_slope since the plant started
+    falling: bool,
+    // z of the soil where the plant started, and the highest z since
+    base_z: f64,
+    peak_z: f64,
+}
+
+impl Band {
+    fn new(config: &BandConfig) -> Band {
+        Band {
+            config: *config,
+            previous_pts: Vec::with_capacity(config.window),
+            in_plant: false,
+            falling: false,
+            base_z: 0.0,
+            peak_z: 0.0,
+        }
+    }
+
+    fn add_pt(&mut self, index: usize, new_pt: &Point) {
+        if self.previous_pts.len() == self.config.window {
+            self.previous_pts.remove(0);
+        }
+        self.previous_pts.push((index, *new_pt));
+    }
+
+    // dz / dx between the first and the last point of the window, None until the window is full
+    // or if its points have the same x.
+    fn slope(&self) -> Option<f64> {
+        if self.previous_pts.len() < self.config.window {
+            return None;
+        }
+        let (_, first) = self.previous_pts[0];
+        let (_, last) = self.previous_pts[self.previous_pts.len() - 1];
+        let d = derivative(&first, &last);
+        if d.is_finite() {
+            Some(d)
+        } else {
+            None
+        }
+    }
+
+    fn process(&mut self, index: usize, curr_pt: &Point, is_plant: &mut [bool]) {
+        self.add_pt(index, curr_pt);
+        let slope = self.slope();
+        if !self.in_plant {
+            if slope.is_some_and(|d| d > self.config.rise_slope) {
+                self.start_plant(is_plant);
+            }
+            return;
+        }
+
+        is_plant[index] = true;
+        self.peak_z = self.peak_z.max(curr_pt.z);
+        let d = match slope {
+            Some(val) => val,
+            None => return,
+        };
+        if d < -self.config.fall_slope {
+            self.falling = true;
+        } else if self.falling && curr_pt.z < self.mid_z() {
+            self.end_plant(is_plant);
+        }
+    }
+
+    // the rise starts at the lowest point of the window, the points after it are plant.
+    fn start_plant(&mut self, is_plant: &mut [bool]) {
+        let foot = self
+            .previous_pts
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.1.z.partial_cmp(&b.1.z).unwrap())
+            .map(|(i, _)| i)
+            .unwrap();
+        self.base_z = self.previous_pts[foot].1.z;
+        self.peak_z = self.base_z;
+        for (index, pt) in &self.previous_pts[foot + 1..] {
+            is_plant[*index] = true;
+            self.peak_z = self.peak_z.max(pt.z);
+        }
+        self.in_plant = true;
+        self.falling = false;
+    }
+
+    // the band is back on the soil, the window points in the lower half are soil. the window is
+    // cleared so that the fall is not measured again.
+    fn end_plant(&mut self, is_plant: &mut [bool]) {
+        let mid_z = self.mid_z();
+        for (index, pt) in &self.previous_pts {
+            if pt.z < mid_z {
+                is_plant[*index] = false;
+            }
+        }
+        self.previous_pts.clear();
+        self.in_plant = false;
+        self.falling = false;
+    }
+
+    fn mid_z(&self) -> f64 {
+        (self.base_z + self.peak_z) / 2.0
+    }
+}
+
+// signed slope dz / dx from a to b, infinite or NaN if they have the same x.
+fn derivative(a: &Point, b: &Point) -> f64 {
+    (b.z - a.z) / (b.x - a.x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{remove_soil, BandConfig};
+    use crate::geometry::Point;
+
+    // hand labelled cloud: a 4 x 4 grid of soil with a little noise, and dome shaped plants of
+    // radius 0.8 and height 1 at the centers. returns the points and whether each is plant.
+    fn labelled_cloud(centers: &[(f64, f64)]) -> (Vec<Point>, Vec<bool>) {
+        let mut points = vec![];
+        let mut labels = vec![];
+        for i in 0..81 {
+            for j in 0..81 {
+                let (x, y) = (i as f64 * 0.05, j as f64 * 0.05);
+                let noise = 0.01 * ((i * 7 + j * 13) % 5) as f64 / 4.0;
+                let mut z = noise;
+                let mut plant = false;
+                for (cx, cy) in centers {
+                    let r = ((x - cx).powi(2) + (y - cy).powi(2)).sqrt() / 0.8;
+                    if r < 1.0 {
+                        z = (1.0 - r * r).sqrt() + 0.05;
+                        plant = true;
+                    }
+                }
+                points.push(Point::new(x, y, z));
+                labels.push(plant);
+            }
+        }
+        (points, labels)
+    }
+
+    // fraction of the points labelled the same way as the hand labels.
+    fn accuracy(points: &[Point], labels: &[bool], plant: &[Point]) -> f64 {
+        let mut correct = 0;
+        for (pt, label) in points.iter().zip(labels) {
+            let kept = plant
+                .iter()
+                .any(|p| p.x == pt.x && p.y == pt.y && p.z == pt.z);
+            if kept == *label {
+                correct += 1;
+            }
+        }
+        correct as f64 / points.len() as f64
+    }
+
+    #[test]
+    fn test_band_segmenter_single_plant() {
+        let (points, labels) = labelled_cloud(&[(2.0, 2.0)]);
+        let config = BandConfig {
+            band_count: 40,
+            ..BandConfig::default()
+        };
+        let plant = remove_soil(points.clone(), &config).unwrap();
+        let plant_count = labels.iter().filter(|l| **l).count();
+        assert!((plant.len() as f64 - plant_count as f64).abs() < 0.1 * plant_count as f64);
+        assert!(accuracy(&points, &labels, &plant) > 0.97);
+        // sorted by x
+        assert!(plant.windows(2).all(|pts| pts[0].x <= pts[1].x));
+    }
+
+    #[test]
+    fn test_band_segmenter_two_plants_in_a_band() {
+        // the bands cross both plants, the boundary is detected again after the first one
+        let (points, labels) = labelled_cloud(&[(1.0, 2.0), (3.0, 2.0)]);
+        let config = BandConfig {
+            band_count: 40,
+            ..BandConfig::default()
+        };
+        let plant = remove_soil(points.clone(), &config).unwrap();
+        assert!(accuracy(&points, &labels, &plant) > 0.97);
+        assert!(plant.iter().any(|pt| pt.x < 2.0));
+        assert!(plant.iter().any(|pt| pt.x > 2.0));
+    }
+
+    #[test]
+    fn test_band_segmenter_flat_soil() {
+        let (points, _) = labelled_cloud(&[]);
+        let plant = remove_soil(points, &BandConfig::default()).unwrap();
+        assert!(plant.is_empty());
+    }
+
+    #[test]
+    fn test_band_config_invalid() {
+        let (points, _) = labelled_cloud(&[]);
+        for config in [
+            BandConfig {
+                band_count: 0,
+                ..BandConfig::default()
+            },
+            BandConfig {
+                window: 1,
+                ..BandConfig::default()
+            },
+            BandConfig {
+                rise_slope: f64::NAN,
+                ..BandConfig::default()
+            },
+        ] {
+            assert!(remove_soil(points.clone(), &config).is_err());
+        }
+        assert!(remove_soil(vec![], &BandConfig::default())
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,267 @@
+use super::{bucket_index, z_range, HeightCut, SoilRemovalConfig, SoilRemovalMode};
+use crate::error::{Error, Result};
+use crate::geometry::{sort_points_by_x, Color, Point};
+use crate::ply_file::PlyPoint;
+
+// how the excess green index that separates the plant from the soil is chosen.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ExgThreshold {
+    // the points with an index above this are plant
+    Fixed(f64),
+    // the cut is halfway between the 2 clusters of the index, found with k-means
+    KMeans,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ColorConfig {
+    pub threshold: ExgThreshold,
+    // with a height cut, the points above it are plant whatever their color (e.g. brown leaves)
+    // and the points below it are plant only if they are green and above the lowest of the
+    // bucket_count buckets of the z range, where the soil is (e.g. green tinted soil)
+    pub height: Option<SoilRemovalConfig>,
+}
+
+impl Default for ColorConfig {
+    fn default() -> Self {
+        ColorConfig {
+            threshold: ExgThreshold::KMeans,
+            height: None,
+        }
+    }
+}
+
+impl ColorConfig {
+    pub fn validate(&self) -> Result<()> {
+        if let ExgThreshold::Fixed(t) = self.threshold {
+            if !t.is_finite() {
+                return Err(Error::InvalidArgument(format!(
+                    "excess green threshold {} is not finite",
+                    t
+                )));
+            }
+        }
+        if let Some(height) = &self.height {
+            height.validate()?;
+            if matches!(
+                height.mode,
+                SoilRemovalMode::Ransac(_) | SoilRemovalMode::Band(_)
+            ) {
+                return Err(Error::InvalidArgument(String::from(
+                    "the color mode can only be combined with a height cut",
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+// excess green 2g - r - b on the chromatic coordinates (r = red / (red + green + blue), ...),
+// in -1 - 2. black is 0.
+pub fn excess_green(color: &Color) -> f64 {
+    let sum = color.red as f64 + color.green as f64 + color.blue as f64;
+    if sum == 0.0 {
+        return 0.0;
+    }
+    (2.0 * color.green as f64 - color.red as f64 - color.blue as f64) / sum
+}
+
+// remove the soil points by their color, every point must have a color. the result is sorted
+// by x.
+pub fn remove_soil_by_color(points: Vec<PlyPoint>, config: &ColorConfig) -> Result<Vec<Point>> {
+    config.validate()?;
+    let mut index = Vec::with_capacity(points.len());
+    for pt in &points {
+        match &pt.color {
+            Some(color) => index.push(excess_green(color)),
+            None => {
+                return Err(Error::InvalidProperty(format!(
+                    "vertex {} has no red, green and blue",
+                    pt.point
+                )))
+            }
+        }
+    }
+    if points.is_empty() {
+        return Ok(vec![]);
+    }
+    let threshold = match config.threshold {
+        ExgThreshold::Fixed(t) => t,
+        ExgThreshold::KMeans => kmeans_threshold(&index),
+    };
+
+    let plain: Vec<Point> = points.iter().map(|pt| pt.point).collect();
+    let height_cut = config
+        .height
+        .as_ref()
+        .and_then(|height| Some((HeightCut::new(&plain, height)?, height.bucket_count)));
+    let (min_z, max_z) = z_range(&plain);
+    let mut result: Vec<Point> = plain
+        .into_iter()
+        .zip(index)
+        .filter(|(pt, exg)| match &height_cut {
+            None => *exg > threshold,
+            Some((cut, bucket_count)) => {
+                cut.is_plant(pt)
+                    || (*exg > threshold && bucket_index(pt.z, min_z, max_z, *bucket_count) > 0)
+            }
+        })
+        .map(|(pt, _)| pt)
+        .collect();
+    // sort_by x
+    sort_points_by_x(&mut result);
+    Ok(result)
+}
+
+// 2-means on the values, starting from the min and the max. returns the midpoint of the 2 means,
+// or the value itself if all the values are the same.
+fn kmeans_threshold(values: &[f64]) -> f64 {
+    let mut low = values.iter().cloned().fold(f64::MAX, f64::min);
+    let mut high = values.iter().cloned().fold(f64::MIN, f64::max);
+    if low == high {
+        return low;
+    }
+    for _ in 0..100 {
+        let threshold = (low + high) / 2.0;
+        let (mut sum_low, mut count_low, mut sum_high, mut count_high) = (0.0, 0, 0.0, 0);
+        for val in values {
+            if *val > threshold {
+                sum_high += val;
+                count_high += 1;
+            } else {
+                sum_low += val;
+                count_low += 1;
+            }
+        }
+        // both clusters keep their extreme value, so neither is empty
+        let new_low = sum_low / count_low as f64;
+        let new_high = sum_high / count_high as f64;
+        if new_low == low && new_high == high {
+            break;
+        }
+        low = new_low;
+        high = new_high;
+    }
+    (low + high) / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{excess_green, kmeans_threshold, remove_soil_by_color, ColorConfig, ExgThreshold};
+    use crate::geometry::{Color, Point};
+    use crate::ply_file::PlyPoint;
+    use crate::soil_removal::{SoilRemovalConfig, SoilRemovalMode};
+    use std::collections::BTreeMap;
+
+    fn ply_point(x: f64, z: f64, color: Color) -> PlyPoint {
+        PlyPoint {
+            point: Point::new(x, 0.0, z),
+            color: Some(color),
+            normal: None,
+            intensity: None,
+            properties: BTreeMap::new(),
+        }
+    }
+
+    // 20 brown soil points at z = 0, 5 of them green tinted, 20 green leaves from z = 0.05 and
+    // 5 brown leaves at the top
+    fn colored_cloud() -> Vec<PlyPoint> {
+        let mut data = vec![];
+        for i in 0..20 {
+            let color = if i < 5 {
+                Color::new(110, 120, 80)
+            } else {
+                Color::new(130, 90, 60)
+            };
+            data.push(ply_point(i as f64, 0.0, color));
+        }
+        for i in 0..20 {
+            data.push(ply_point(
+                i as f64,
+                0.05 + i as f64 * 0.1,
+                Color::new(60, 160, 50),
+            ));
+        }
+        for i in 0..5 {
+            data.push(ply_point(
+                i as f64,
+                2.0 + i as f64 * 0.1,
+                Color::new(130, 100, 70),
+            ));
+        }
+        data
+    }
+
+    #[test]
+    fn test_excess_green() {
+        assert_eq!(excess_green(&Color::new(0, 0, 0)), 0.0);
+        assert_eq!(excess_green(&Color::new(0, 255, 0)), 2.0);
+        assert_eq!(excess_green(&Color::new(100, 100, 100)), 0.0);
+        assert!(excess_green(&Color::new(130, 90, 60)) < 0.0);
+    }
+
+    #[test]
+    fn test_kmeans_threshold() {
+        let t = kmeans_threshold(&[0.0, 0.1, 0.05, 1.0, 0.9, 0.95]);
+        assert!(t > 0.1 && t < 0.9);
+        assert_eq!(kmeans_threshold(&[0.5, 0.5]), 0.5);
+    }
+
+    #[test]
+    fn test_remove_soil_by_color() {
+        // the green tinted soil is closer to the soil than to the leaves
+        let result = remove_soil_by_color(colored_cloud(), &ColorConfig::default()).unwrap();
+        assert_eq!(result.len(), 20);
+        assert!(result.iter().all(|pt| pt.z > 0.0 && pt.z < 2.0));
+        assert!(result.windows(2).all(|pts| pts[0].x <= pts[1].x));
+
+        let fixed = ColorConfig {
+            threshold: ExgThreshold::Fixed(-0.5),
+            height: None,
+        };
+        assert_eq!(
+            remove_soil_by_color(colored_cloud(), &fixed).unwrap().len(),
+            45
+        );
+    }
+
+    #[test]
+    fn test_remove_soil_by_color_with_height() {
+        // the height cut at 1.0 keeps the brown leaves, the low green leaves are kept by color
+        let config = ColorConfig {
+            threshold: ExgThreshold::KMeans,
+            height: Some(SoilRemovalConfig {
+                mode: SoilRemovalMode::Height(1.0),
+                bucket_count: 100,
+            }),
+        };
+        let result = remove_soil_by_color(colored_cloud(), &config).unwrap();
+        assert_eq!(result.len(), 25);
+        assert!(result.iter().all(|pt| pt.z > 0.0));
+
+        // a green point in the soil is below the cut and in the lowest bucket, it is removed
+        let mut data = colored_cloud();
+        data.push(ply_point(3.5, 0.0, Color::new(60, 160, 50)));
+        let result = remove_soil_by_color(data.clone(), &config).unwrap();
+        assert_eq!(result.len(), 25);
+        assert!(result.iter().all(|pt| pt.z > 0.0));
+        // kept by its color alone without the height cut
+        let result = remove_soil_by_color(data, &ColorConfig::default()).unwrap();
+        assert_eq!(result.len(), 21);
+
+        let ransac = ColorConfig {
+            threshold: ExgThreshold::KMeans,
+            height: Some(SoilRemovalConfig {
+                mode: SoilRemovalMode::Ransac(Default::default()),
+                bucket_count: 100,
+            }),
+        };
+        assert!(remove_soil_by_color(colored_cloud(), &ransac).is_err());
+    }
+
+    #[test]
+    fn test_remove_soil_by_color_no_color() {
+        let mut data = colored_cloud();
+        data[3].color = None;
+        assert!(remove_soil_by_color(data, &ColorConfig::default()).is_err());
+    }
+}
@@ -0,0 +1,387 @@
+mod band;
+mod color;
+mod ransac;
+
+use crate::error::{Error, Result};
+use crate::geometry::{sort_points_by_x, Point};
+
+pub use band::{remove_soil, BandConfig};
+pub use color::{excess_green, remove_soil_by_color, ColorConfig, ExgThreshold};
+pub use ransac::{fit_ground_plane, level_points, Plane, RansacConfig};
+
+use ransac::is_soil;
+
+pub const DEFAULT_BUCKET_COUNT: usize = 100;
+pub const DEFAULT_SOIL_BUCKETS: usize = 43;
+
+// how the height that separates the soil from the plant is chosen.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SoilRemovalMode {
+    // the points in the lowest soil_buckets + 1 buckets of the z range are soil
+    Buckets { soil_buckets: usize },
+    // the points below this percentile (0 - 100) of the z values are soil
+    Percentile(f64),
+    // the points at or below this z are soil
+    Height(f64),
+    // the cut is the valley of the z histogram, found with Otsu's method
+    Otsu,
+    // the soil is the dominant ground plane fitted with RANSAC, for sloped or tilted beds
+    Ransac(RansacConfig),
+    // the plant is where the ground rises steeply, found band by band along x
+    Band(BandConfig),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SoilRemovalConfig {
+    pub mode: SoilRemovalMode,
+    // number of buckets the z range is split into, for the Buckets and Otsu modes
+    pub bucket_count: usize,
+}
+
+// the plant points, and the ground plane if the soil is removed with RANSAC.
+#[derive(Clone, Debug)]
+pub struct SoilSegmentation {
+    pub plant: Vec<Point>,
+    pub ground_plane: Option<Plane>,
+}
+
+impl Default for SoilRemovalConfig {
+    // 100 buckets, the lowest 44 are soil
+    fn default() -> Self {
+        SoilRemovalConfig {
+            mode: SoilRemovalMode::Buckets {
+                soil_buckets: DEFAULT_SOIL_BUCKETS,
+            },
+            bucket_count: DEFAULT_BUCKET_COUNT,
+        }
+    }
+}
+
+impl SoilRemovalConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.bucket_count == 0 {
+            return Err(Error::InvalidArgument(String::from(
+                "bucket count must be at least 1",
+            )));
+        }
+        match self.mode {
+            SoilRemovalMode::Percentile(p) if !(0.0..=100.0).contains(&p) => Err(
+                Error::InvalidArgument(format!("percentile {} is not in 0 - 100", p)),
+            ),
+            SoilRemovalMode::Height(z) if !z.is_finite() => Err(Error::InvalidArgument(format!(
+                "soil height {} is not finite",
+                z
+            ))),
+            SoilRemovalMode::Ransac(ransac) => ransac.validate(),
+            SoilRemovalMode::Band(band) => band.validate(),
+            _ => Ok(()),
+        }
+    }
+}
+
+// remove the soil with the default config.
+pub fn remove_soil_simple(points: Vec<Point>) -> Vec<Point> {
+    // the default config is always valid
+    remove_soil_with(points, &SoilRemovalConfig::default()).unwrap()
+}
+
+// remove the soil points, the result is sorted by x.
+pub fn remove_soil_with(points: Vec<Point>, config: &SoilRemovalConfig) -> Result<Vec<Point>> {
+    Ok(segment_soil(points, config)?.plant)
+}
+
+// remove the soil points and report the ground plane, the plant points are sorted by x.
+// with RANSAC and level set, the plant points are moved so that the ground plane is z = 0.
+pub fn segment_soil(points: Vec<Point>, config: &SoilRemovalConfig) -> Result<SoilSegmentation> {
+    config.validate()?;
+    if points.is_empty() {
+        return Ok(SoilSegmentation {
+            plant: points,
+            ground_plane: None,
+        });
+    }
+    if let Some(cut) = HeightCut::new(&points, config) {
+        return Ok(SoilSegmentation {
+            plant: keep_plant(points, |pt| cut.is_plant(pt)),
+            ground_plane: None,
+        });
+    }
+    let plant = match config.mode {
+        SoilRemovalMode::Ransac(ransac) => {
+            let plane = fit_ground_plane(&points, &ransac)?;
+            let mut plant =
+                keep_plant(points, |pt| !is_soil(&plane, pt, ransac.distance_tolerance));
+            if ransac.level {
+                plant = level_points(&plant, &plane);
+            }
+            return Ok(SoilSegmentation {
+                plant,
+                ground_plane: Some(plane),
+            });
+        }
+        SoilRemovalMode::Band(band) => remove_soil(points, &band)?,
+        _ => unreachable!("the height modes are handled by HeightCut"),
+    };
+    Ok(SoilSegmentation {
+        plant,
+        ground_plane: None,
+    })
+}
+
+// the modes that cut the cloud at a height, they decide point by point.
+enum HeightCut {
+    Buckets {
+        min_z: f64,
+        max_z: f64,
+        bucket_count: usize,
+        soil_buckets: usize,
+    },
+    // the points above this z are plant
+    Above(f64),
+}
+
+impl HeightCut {
+    // None for the modes that are not a height cut. the points must not be empty.
+    fn new(points: &[Point], config: &SoilRemovalConfig) -> Option<HeightCut> {
+        let (min_z, max_z) = z_range(points);
+        let bucket_count = config.bucket_count;
+        match config.mode {
+            SoilRemovalMode::Buckets { soil_buckets } => Some(HeightCut::Buckets {
+                min_z,
+                max_z,
+                bucket_count,
+                soil_buckets,
+            }),
+            SoilRemovalMode::Otsu => Some(HeightCut::Buckets {
+                min_z,
+                max_z,
+                bucket_count,
+                soil_buckets: otsu_soil_buckets(points, min_z, max_z, bucket_count),
+            }),
+            SoilRemovalMode::Percentile(p) => Some(HeightCut::Above(percentile_z(points, p))),
+            SoilRemovalMode::Height(cut) => Some(HeightCut::Above(cut)),
+            SoilRemovalMode::Ransac(_) | SoilRemovalMode::Band(_) => None,
+        }
+    }
+
+    fn is_plant(&self, pt: &Point) -> bool {
+        match *self {
+            HeightCut::Buckets {
+                min_z,
+                max_z,
+                bucket_count,
+                soil_buckets,
+            } => bucket_index(pt.z, min_z, max_z, bucket_count) > soil_buckets,
+            HeightCut::Above(cut) => pt.z > cut,
+        }
+    }
+}
+
+fn keep_plant<F: Fn(&Point) -> bool>(mut points: Vec<Point>, is_plant: F) -> Vec<Point> {
+    // sort_by x
+    sort_points_by_x(&mut points);
+    points.retain(is_plant);
+    points
+}
+
+fn z_range(points: &[Point]) -> (f64, f64) {
+    let mut min_z: f64 = f64::MAX;
+    let mut max_z: f64 = f64::MIN;
+    for pt in points {
+        if pt.z < min_z {
+            min_z = pt.z
+        }
+        if pt.z > max_z {
+            max_z = pt.z
+        }
+    }
+    (min_z, max_z)
+}
+
+// split the z range into bucket_count buckets of the same height, the highest point is in the
+// last bucket.
+fn bucket_index(z: f64, min_z: f64, max_z: f64, bucket_count: usize) -> usize {
+    let bucket_width = (max_z - min_z) / (bucket_count as f64);
+    let index = ((z - min_z) / bucket_width) as usize;
+    index.min(bucket_count - 1)
+}
+
+// z value below which p percent of the points are, interpolated between the 2 closest points.
+fn percentile_z(points: &[Point], p: f64) -> f64 {
+    let mut z: Vec<f64> = points.iter().map(|pt| pt.z).collect();
+    z.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let rank = p / 100.0 * (z.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    z[lower] + (z[upper] - z[lower]) * (rank - lower as f64)
+}
+
+// Otsu's method on the z histogram: the split that maximizes the variance between the 2 classes.
+// returns the index of the last soil bucket.
+fn otsu_soil_buckets(points: &[Point], min_z: f64, max_z: f64, bucket_count: usize) -> usize {
+    let mut histogram = vec![0usize; bucket_count];
+    for pt in points {
+        histogram[bucket_index(pt.z, min_z, max_z, bucket_count)] += 1;
+    }
+    let total = points.len() as f64;
+    let sum_all: f64 = histogram
+        .iter()
+        .enumerate()
+        .map(|(i, count)| i as f64 * *count as f64)
+        .sum();
+
+    let mut best = 0;
+    let mut best_variance = -1.0;
+    let mut count_below = 0.0;
+    let mut sum_below = 0.0;
+    for (i, count) in histogram.iter().enumerate() {
+        count_below += *count as f64;
+        sum_below += i as f64 * *count as f64;
+        let count_above = total - count_below;
+        if count_below == 0.0 || count_above == 0.0 {
+            continue;
+        }
+        let mean_below = sum_below / count_below;
+        let mean_above = (sum_all - sum_below) / count_above;
+        let variance = count_below * count_above * (mean_below - mean_above).powi(2);
+        if variance > best_variance {
+            best_variance = variance;
+            best = i;
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        remove_soil_simple, remove_soil_with, segment_soil, RansacConfig, SoilRemovalConfig,
+        SoilRemovalMode,
+    };
+    use crate::geometry::Point;
+
+    // a flat soil layer of 60 points around z = 0 and a plant of 40 points from z = 5 to z = 9
+    fn soil_and_plant() -> Vec<Point> {
+        let mut data = vec![];
+        for i in 0..60 {
+            data.push(Point::new(i as f64, 0.0, (i % 3) as f64 * 0.1));
+        }
+        for i in 0..40 {
+            data.push(Point::new(i as f64, 1.0, 5.0 + i as f64 * 0.1));
+        }
+        data
+    }
+
+    fn config(mode: SoilRemovalMode) -> SoilRemovalConfig {
+        SoilRemovalConfig {
+            mode,
+            bucket_count: 100,
+        }
+    }
+
+    fn assert_plant_only(result: &[Point]) {
+        assert_eq!(result.len(), 40);
+        assert!(result.iter().all(|pt| pt.z >= 5.0));
+    }
+
+    #[test]
+    fn test_remove_soil_default() {
+        // the default cut is at 44% of the z range, z = 3.96
+        let result = remove_soil_simple(soil_and_plant());
+        assert_plant_only(&result);
+        // sorted by x
+        assert!(result.windows(2).all(|pts| pts[0].x <= pts[1].x));
+    }
+
+    #[test]
+    fn test_remove_soil_modes() {
+        let data = soil_and_plant();
+        for mode in [
+            SoilRemovalMode::Buckets { soil_buckets: 10 },
+            SoilRemovalMode::Percentile(60.0),
+            SoilRemovalMode::Height(2.5),
+            SoilRemovalMode::Otsu,
+        ] {
+            let result = remove_soil_with(data.clone(), &config(mode)).unwrap();
+            assert_plant_only(&result);
+        }
+        // a lower cut keeps the plant but not the soil, the percentile cut is 59.4
+        let result =
+            remove_soil_with(data.clone(), &config(SoilRemovalMode::Percentile(59.0))).unwrap();
+        assert_eq!(result.len(), 40);
+        let result = remove_soil_with(data, &config(SoilRemovalMode::Height(-1.0))).unwrap();
+        assert_eq!(result.len(), 100);
+    }
+
+    #[test]
+    fn test_remove_soil_otsu_thick_soil() {
+        // the soil layer is half of the z range, a fixed 44% cut of the z range keeps some of it
+        let mut data = vec![];
+        for i in 0..61 {
+            data.push(Point::new(i as f64, 0.0, i as f64 * 0.01));
+        }
+        for i in 0..21 {
+            data.push(Point::new(i as f64, 1.0, 1.0 + i as f64 * 0.01));
+        }
+        let fixed = remove_soil_simple(data.clone());
+        assert!(fixed.len() > 21);
+        let otsu = remove_soil_with(data, &config(SoilRemovalMode::Otsu)).unwrap();
+        assert_eq!(otsu.len(), 21);
+        assert!(otsu.iter().all(|pt| pt.z >= 1.0));
+    }
+
+    #[test]
+    fn test_segment_soil_ransac_sloped() {
+        // ground z = 0.3 x, and a plant of 30 points 1 to 4 above the ground at x = 5
+        let mut data = vec![];
+        for i in 0..21 {
+            for j in 0..21 {
+                let (x, y) = (i as f64 * 0.5, j as f64 * 0.5);
+                data.push(Point::new(x, y, 0.3 * x));
+            }
+        }
+        for i in 0..30 {
+            data.push(Point::new(
+                5.0,
+                5.0 + i as f64 * 0.01,
+                1.5 + 1.0 + i as f64 * 0.1,
+            ));
+        }
+        // a z cut leaves the high side of the bed
+        let fixed = remove_soil_simple(data.clone());
+        assert!(fixed.len() > 30);
+
+        let ransac = RansacConfig {
+            level: true,
+            ..RansacConfig::default()
+        };
+        let result = segment_soil(data, &config(SoilRemovalMode::Ransac(ransac))).unwrap();
+        assert_eq!(result.plant.len(), 30);
+        let plane = result.ground_plane.unwrap();
+        assert!((plane.tilt_degrees() - 0.3f64.atan().to_degrees()).abs() < 0.000001);
+        // leveled, the plant is 1 to 4 above the ground along z (times the cosine of the slope)
+        let cos = 0.3f64.atan().cos();
+        for pt in &result.plant {
+            assert!(pt.z > 1.0 * cos - 0.000001 && pt.z < 4.0 * cos, "{}", pt);
+        }
+    }
+
+    #[test]
+    fn test_remove_soil_invalid_config() {
+        let data = soil_and_plant();
+        assert!(
+            remove_soil_with(data.clone(), &config(SoilRemovalMode::Percentile(101.0))).is_err()
+        );
+        assert!(
+            remove_soil_with(data.clone(), &config(SoilRemovalMode::Height(f64::NAN))).is_err()
+        );
+        let zero_buckets = SoilRemovalConfig {
+            mode: SoilRemovalMode::Otsu,
+            bucket_count: 0,
+        };
+        assert!(remove_soil_with(data, &zero_buckets).is_err());
+        assert!(remove_soil_with(vec![], &config(SoilRemovalMode::Otsu))
+            .unwrap()
+            .is_empty());
+    }
+}
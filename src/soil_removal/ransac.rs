@@ -0,0 +1,331 @@
+use crate::error::{Error, Result};
+use crate::geometry::{Point, Vec3D};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+// plane normal . p = offset, the normal is a unit vector pointing up (normal.z > 0).
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct Plane {
+    pub normal: Vec3D,
+    pub offset: f64,
+}
+
+impl Plane {
+    // plane through 3 points, None if the points are collinear.
+    pub fn from_points(p1: &Point, p2: &Point, p3: &Point) -> Option<Plane> {
+        let (ax, ay, az) = (p2.x - p1.x, p2.y - p1.y, p2.z - p1.z);
+        let (bx, by, bz) = (p3.x - p1.x, p3.y - p1.y, p3.z - p1.z);
+        let normal = Vec3D::new(ay * bz - az * by, az * bx - ax * bz, ax * by - ay * bx);
+        Plane::from_normal(normal, p1)
+    }
+
+    // plane with the normal through the point, None if the normal is 0.
+    fn from_normal(normal: Vec3D, pt: &Point) -> Option<Plane> {
+        let len = (normal.x * normal.x + normal.y * normal.y + normal.z * normal.z).sqrt();
+        if len == 0.0 || !len.is_finite() {
+            return None;
+        }
+        // the normal points up
+        let sign = if normal.z < 0.0 { -1.0 } else { 1.0 };
+        let normal = Vec3D::new(
+            sign * normal.x / len,
+            sign * normal.y / len,
+            sign * normal.z / len,
+        );
+        let offset = normal.x * pt.x + normal.y * pt.y + normal.z * pt.z;
+        Some(Plane { normal, offset })
+    }
+
+    // distance to the plane, positive above the plane.
+    pub fn signed_distance(&self, pt: &Point) -> f64 {
+        self.normal.x * pt.x + self.normal.y * pt.y + self.normal.z * pt.z - self.offset
+    }
+
+    // angle between the normal and the z axis, in degrees.
+    pub fn tilt_degrees(&self) -> f64 {
+        self.normal.z.clamp(-1.0, 1.0).acos().to_degrees()
+    }
+}
+
+impl Display for Plane {
+    // a x + b y + c z + d = 0
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} x + {} y + {} z + {} = 0",
+            self.normal.x, self.normal.y, self.normal.z, -self.offset
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RansacConfig {
+    // number of random planes tried
+    pub iterations: usize,
+    // points closer to the ground plane than this (or below it) are soil
+    pub distance_tolerance: f64,
+    // planes tilted more than this from the x-y plane are not ground, e.g. a leaf
+    pub max_tilt_degrees: f64,
+    // rotate and translate the plant so that the ground plane is z = 0
+    pub level: bool,
+    // seed of the random sampling, the fit is the same for the same seed
+    pub seed: u64,
+}
+
+impl Default for RansacConfig {
+    fn default() -> Self {
+        RansacConfig {
+            iterations: 1000,
+            distance_tolerance: 0.01,
+            max_tilt_degrees: 30.0,
+            level: false,
+            seed: 0,
+        }
+    }
+}
+
+impl RansacConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.iterations == 0 {
+            return Err(Error::InvalidArgument(String::from(
+                "RANSAC needs at least 1 iteration",
+            )));
+        }
+        if !(self.distance_tolerance >= 0.0 && self.distance_tolerance.is_finite()) {
+            return Err(Error::InvalidArgument(format!(
+                "distance tolerance {} must be positive",
+                self.distance_tolerance
+            )));
+        }
+        if !(0.0..=90.0).contains(&self.max_tilt_degrees) {
+            return Err(Error::InvalidArgument(format!(
+                "max tilt {} is not in 0 - 90",
+                self.max_tilt_degrees
+            )));
+        }
+        Ok(())
+    }
+}
+
+// fit the dominant ground plane: the plane (not tilted more than max_tilt_degrees) with the most
+// points within distance_tolerance, refined by least squares on those points.
+pub fn fit_ground_plane(points: &[Point], config: &RansacConfig) -> Result<Plane> {
+    config.validate()?;
+    if points.len() < 3 {
+        return Err(Error::NotEnoughPoints {
+            required: 3,
+            found: points.len(),
+        });
+    }
+    let min_normal_z = config.max_tilt_degrees.to_radians().cos();
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut best: Option<(Plane, usize)> = None;
+    for _ in 0..config.iterations {
+        let i = rng.gen_range(0..points.len());
+        let j = rng.gen_range(0..points.len());
+        let k = rng.gen_range(0..points.len());
+        if i == j || j == k || i == k {
+            continue;
+        }
+        let plane = match Plane::from_points(&points[i], &points[j], &points[k]) {
+            Some(val) => val,
+            None => continue,
+        };
+        if plane.normal.z < min_normal_z {
+            continue;
+        }
+        let inliers = count_inliers(points, &plane, config.distance_tolerance);
+        if best.map(|(_, count)| inliers > count).unwrap_or(true) {
+            best = Some((plane, inliers));
+        }
+    }
+    let plane = match best {
+        Some((val, _)) => val,
+        None => {
+            return Err(Error::Degenerate(String::from(
+                "no ground plane found, the points are collinear or too tilted",
+            )))
+        }
+    };
+    let inliers: Vec<Point> = points
+        .iter()
+        .filter(|pt| plane.signed_distance(pt).abs() <= config.distance_tolerance)
+        .copied()
+        .collect();
+    Ok(least_squares_plane(&inliers).unwrap_or(plane))
+}
+
+// soil points are below the plane or within the tolerance.
+pub fn is_soil(plane: &Plane, pt: &Point, distance_tolerance: f64) -> bool {
+    plane.signed_distance(pt) <= distance_tolerance
+}
+
+// rotate the points so that the plane normal is the z axis, and move the plane to z = 0.
+// z is then the height above the ground.
+pub fn level_points(points: &[Point], plane: &Plane) -> Vec<Point> {
+    let n = &plane.normal;
+    // rotation axis n X z = (n.y, -n.x, 0), Rodrigues' formula
+    let (vx, vy) = (n.y, -n.x);
+    let s2 = vx * vx + vy * vy;
+    let c = n.z;
+    let k = if s2 == 0.0 { 0.0 } else { (1.0 - c) / s2 };
+    let rotation = [
+        [1.0 - k * vy * vy, k * vx * vy, vy],
+        [k * vx * vy, 1.0 - k * vx * vx, -vx],
+        [-vy, vx, 1.0 - k * s2],
+    ];
+    points
+        .iter()
+        .map(|pt| {
+            let p = [pt.x, pt.y, pt.z];
+            let dot = |row: &[f64; 3]| row[0] * p[0] + row[1] * p[1] + row[2] * p[2];
+            Point::new(
+                dot(&rotation[0]),
+                dot(&rotation[1]),
+                dot(&rotation[2]) - plane.offset,
+            )
+        })
+        .collect()
+}
+
+fn count_inliers(points: &[Point], plane: &Plane, distance_tolerance: f64) -> usize {
+    points
+        .iter()
+        .filter(|pt| plane.signed_distance(pt).abs() <= distance_tolerance)
+        .count()
+}
+
+// fit z = a x + b y + c, None if the points do not span a plane.
+fn least_squares_plane(points: &[Point]) -> Option<Plane> {
+    if points.len() < 3 {
+        return None;
+    }
+    // centered on the centroid for a better conditioned system
+    let count = points.len() as f64;
+    let cx = points.iter().map(|pt| pt.x).sum::<f64>() / count;
+    let cy = points.iter().map(|pt| pt.y).sum::<f64>() / count;
+    let cz = points.iter().map(|pt| pt.z).sum::<f64>() / count;
+    let (mut sxx, mut sxy, mut syy, mut sxz, mut syz) = (0.0, 0.0, 0.0, 0.0, 0.0);
+    for pt in points {
+        let (x, y, z) = (pt.x - cx, pt.y - cy, pt.z - cz);
+        sxx += x * x;
+        sxy += x * y;
+        syy += y * y;
+        sxz += x * z;
+        syz += y * z;
+    }
+    let det = sxx * syy - sxy * sxy;
+    if det.abs() <= f64::EPSILON * (sxx * syy).abs() {
+        return None;
+    }
+    let a = (sxz * syy - syz * sxy) / det;
+    let b = (syz * sxx - sxz * sxy) / det;
+    Plane::from_normal(Vec3D::new(-a, -b, 1.0), &Point::new(cx, cy, cz))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fit_ground_plane, level_points, Plane, RansacConfig};
+    use crate::geometry::Point;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    const TOLERANCE: f64 = 0.000001;
+
+    // ground z = 0.2 x - 0.1 y + 1 with some noise, and a plant standing on it at (5, 5)
+    fn sloped_bed() -> Vec<Point> {
+        let mut rng = StdRng::seed_from_u64(7);
+        let ground = |x: f64, y: f64| 0.2 * x - 0.1 * y + 1.0;
+        let mut data = vec![];
+        for i in 0..30 {
+            for j in 0..30 {
+                let (x, y) = (i as f64 / 3.0, j as f64 / 3.0);
+                let noise: f64 = rng.gen_range(-0.002..0.002);
+                data.push(Point::new(x, y, ground(x, y) + noise));
+            }
+        }
+        for i in 0..200 {
+            let t = i as f64 * 0.1;
+            let (x, y) = (5.0 + t.cos() * 0.5, 5.0 + t.sin() * 0.5);
+            data.push(Point::new(x, y, ground(x, y) + 0.05 + i as f64 * 0.01));
+        }
+        data
+    }
+
+    #[test]
+    fn test_fit_ground_plane_sloped() {
+        let data = sloped_bed();
+        let plane = fit_ground_plane(&data, &RansacConfig::default()).unwrap();
+        // normal of z = 0.2 x - 0.1 y + 1 is (-0.2, 0.1, 1) normalized
+        let len = (0.04f64 + 0.01 + 1.0).sqrt();
+        assert!((plane.normal.x + 0.2 / len).abs() < 0.001, "{}", plane);
+        assert!((plane.normal.y - 0.1 / len).abs() < 0.001, "{}", plane);
+        assert!((plane.normal.z - 1.0 / len).abs() < 0.001, "{}", plane);
+        assert!((plane.offset - 1.0 / len).abs() < 0.001, "{}", plane);
+        // the same seed gives the same plane
+        let again = fit_ground_plane(&data, &RansacConfig::default()).unwrap();
+        assert_eq!(plane.offset, again.offset);
+    }
+
+    #[test]
+    fn test_fit_ground_plane_max_tilt() {
+        // a wall x = 0 with more points than the ground
+        let mut data = vec![];
+        for i in 0..20 {
+            for j in 0..20 {
+                data.push(Point::new(0.0, i as f64, 1.0 + j as f64));
+            }
+        }
+        for i in 0..15 {
+            for j in 0..15 {
+                data.push(Point::new(1.0 + i as f64, j as f64, 0.0));
+            }
+        }
+        let plane = fit_ground_plane(&data, &RansacConfig::default()).unwrap();
+        assert!(plane.tilt_degrees() < TOLERANCE, "{}", plane);
+        assert!(plane.offset.abs() < TOLERANCE, "{}", plane);
+    }
+
+    #[test]
+    fn test_level_points() {
+        let plane = Plane::from_points(
+            &Point::new(0.0, 0.0, 1.0),
+            &Point::new(1.0, 0.0, 1.2),
+            &Point::new(0.0, 1.0, 0.9),
+        )
+        .unwrap();
+        let data = vec![
+            Point::new(3.0, 4.0, 0.2 * 3.0 - 0.1 * 4.0 + 1.0),
+            Point::new(0.0, 0.0, 3.0),
+        ];
+        let leveled = level_points(&data, &plane);
+        // on the plane
+        assert!(leveled[0].z.abs() < TOLERANCE);
+        // height above the plane along its normal
+        assert!((leveled[1].z - plane.signed_distance(&data[1])).abs() < TOLERANCE);
+        // the rotation keeps the distances
+        let d = |a: &Point, b: &Point| {
+            ((a.x - b.x).powi(2) + (a.y - b.y).powi(2) + (a.z - b.z).powi(2)).sqrt()
+        };
+        assert!((d(&data[0], &data[1]) - d(&leveled[0], &leveled[1])).abs() < TOLERANCE);
+
+        // a level plane only moves the points
+        let flat = Plane::from_points(
+            &Point::new(0.0, 0.0, 2.0),
+            &Point::new(1.0, 0.0, 2.0),
+            &Point::new(0.0, 1.0, 2.0),
+        )
+        .unwrap();
+        let leveled = level_points(&[Point::new(1.0, 2.0, 5.0)], &flat);
+        assert_eq!((leveled[0].x, leveled[0].y, leveled[0].z), (1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn test_fit_ground_plane_degenerate() {
+        let collinear: Vec<Point> = (0..10).map(|i| Point::new(i as f64, 0.0, 0.0)).collect();
+        assert!(fit_ground_plane(&collinear, &RansacConfig::default()).is_err());
+        assert!(fit_ground_plane(&collinear[..2], &RansacConfig::default()).is_err());
+    }
+}
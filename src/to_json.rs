@@ -1,15 +1,15 @@
+use crate::batch::BatchRow;
+use crate::error::{Error, Result};
 use crate::geometry::Point;
+use crate::plant_traits::PlantTraits;
 use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::io::prelude::*;
-use std::path::Path;
 
 #[derive(Serialize, Deserialize)]
 struct VecPts(Vec<Point>);
 
-pub fn dump_to_json(pts: &Vec<Point>) {
-    println!("rebased and dump to json");
-
+pub fn dump_to_json(pts: &[Point], path: &str) -> Result<()> {
     let mut min_x = f64::MAX;
     let mut min_y = f64::MAX;
     for pt in pts {
@@ -20,7 +20,6 @@ pub fn dump_to_json(pts: &Vec<Point>) {
             min_y = pt.y
         }
     }
-    println!("{}, {}", min_x, min_y);
 
     let mut rebased = vec![];
     for pt in pts {
@@ -32,20 +31,30 @@ pub fn dump_to_json(pts: &Vec<Point>) {
         rebased.push(new_pt);
     }
 
-    let foo: VecPts = VecPts { 0: rebased };
-    let json_str = serde_json::to_string(&foo).unwrap();
+    let rebased_pts: VecPts = VecPts(rebased);
+    let json_str = serde_json::to_string(&rebased_pts).unwrap();
+    write_json(&json_str, path)
+}
 
-    let path = Path::new("input.json");
-    let display = path.display();
+pub fn dump_traits_to_json(traits: &PlantTraits, path: &str) -> Result<()> {
+    let json_str = serde_json::to_string_pretty(traits).unwrap();
+    write_json(&json_str, path)
+}
+
+pub fn dump_batch_to_json(rows: &[BatchRow], path: &str) -> Result<()> {
+    let json_str = serde_json::to_string_pretty(rows).unwrap();
+    write_json(&json_str, path)
+}
 
+fn write_json(json_str: &str, path: &str) -> Result<()> {
     // Open a file in write-only mode, returns `io::Result<File>`
-    let mut file = match File::create(&path) {
-        Err(why) => panic!("couldn't create {}: {}", display, why),
+    let mut file = match File::create(path) {
+        Err(why) => return Err(Error::Io(String::from(path), why)),
         Ok(file) => file,
     };
 
     match file.write_all(json_str.as_bytes()) {
-        Err(why) => panic!("couldn't write to {}: {}", display, why),
-        Ok(_) => println!("successfully wrote to {}", display),
+        Err(why) => Err(Error::Io(String::from(path), why)),
+        Ok(_) => Ok(()),
     }
 }